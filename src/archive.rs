@@ -0,0 +1,100 @@
+use uuid::Uuid;
+
+use crate::{
+    store::{LoadError, StoreChange, TodoStore},
+    todo::Todo,
+};
+
+/// The done todos taken out of the lists, in the order they were archived, kept in a store of
+/// their own so that the todos that are still worked on stay small
+#[derive(Default)]
+pub struct Archive {
+    todos: Vec<Todo>,
+    /// Where the archived todos are kept, nothing being written without one
+    store: Option<Box<dyn TodoStore>>,
+    /// Set when the stored archive couldn't be read or must not be written back, nothing being
+    /// archived or unarchived until it is fixed
+    read_only_reason: Option<String>,
+}
+
+impl Archive {
+    /// Reads the archived todos. An archive that is corrupt or written by a newer build is opened
+    /// read-only rather than keeping the todos from being opened
+    pub fn open(mut store: Box<dyn TodoStore>) -> Result<Archive, String> {
+        let (todos, read_only_reason) = match store.load() {
+            Ok(todos) => (todos, None),
+            Err(LoadError::Io(error)) => return Err(error.to_string()),
+            Err(LoadError::Corrupt(data)) => (
+                Vec::new(),
+                Some(format!(
+                    "{} at line {}, column {}",
+                    data.message, data.line, data.column
+                )),
+            ),
+            Err(LoadError::ReadOnly { todos, reason }) => (todos, Some(reason)),
+        };
+        let read_only_reason = read_only_reason
+            .map(|reason| format!("the archive '{}' is read-only, {reason}", store.location()));
+        Ok(Archive {
+            todos,
+            store: Some(store),
+            read_only_reason,
+        })
+    }
+
+    pub fn get_read_only_reason(&self) -> Option<&String> {
+        self.read_only_reason.as_ref()
+    }
+
+    fn write(&mut self, change: StoreChange) -> Result<(), String> {
+        if let Some(reason) = &self.read_only_reason {
+            return Err(reason.clone());
+        }
+        match &mut self.store {
+            Some(store) => store.apply(change, &self.todos).map_err(|error| {
+                format!(
+                    "couldn't write the archive to '{}': {error}",
+                    store.location()
+                )
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn todos(&self) -> &[Todo] {
+        &self.todos
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&Todo> {
+        self.todos.iter().find(|todo| todo.id == id)
+    }
+
+    pub fn position(&self, id: Uuid) -> Option<usize> {
+        self.todos.iter().position(|todo| todo.id == id)
+    }
+
+    /// Puts the todo at its position, or at the end if the archive got shorter since. The
+    /// archive is left as it was if it can't be written
+    pub fn insert(&mut self, idx: usize, todo: Todo) -> Result<(), String> {
+        let idx = idx.min(self.todos.len());
+        self.todos.insert(idx, todo);
+        self.write(StoreChange::Added(idx)).inspect_err(|_| {
+            self.todos.remove(idx);
+        })
+    }
+
+    pub fn remove(&mut self, id: Uuid) -> Result<Option<Todo>, String> {
+        let idx = match self.position(id) {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+        let todo = self.todos.remove(idx);
+        match self.write(StoreChange::Deleted(idx)) {
+            Ok(()) => Ok(Some(todo)),
+            Err(error) => {
+                self.todos.insert(idx, todo);
+                Err(error)
+            }
+        }
+    }
+}
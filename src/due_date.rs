@@ -0,0 +1,300 @@
+use chrono::{
+    DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone,
+};
+use ratatui::style::Color;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+pub mod natural;
+pub mod recurrence;
+
+/// Todos due in less than this many days are shown as due soon
+pub const DUE_SOON_DAYS: i64 = 3;
+
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%d/%m/%Y"];
+const TIME_FORMATS: &[&str] = &["%H:%M", "%H:%M:%S"];
+
+/// When a todo is due, the time and timezone being optional.
+///
+/// Without a time, the todo is due at the end of the day, and without a timezone the
+/// local one is used
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DueDate {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+    pub offset: Option<FixedOffset>,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum DueStatus {
+    Overdue,
+    Today,
+    Soon,
+    Later,
+}
+
+impl DueStatus {
+    pub fn color(&self) -> Option<Color> {
+        match self {
+            DueStatus::Overdue => Some(Color::LightRed),
+            DueStatus::Today => Some(Color::Yellow),
+            DueStatus::Soon => Some(Color::Cyan),
+            DueStatus::Later => None,
+        }
+    }
+
+    pub fn label(&self) -> Option<&'static str> {
+        match self {
+            DueStatus::Overdue => Some("overdue"),
+            DueStatus::Today => Some("today"),
+            DueStatus::Soon => Some("soon"),
+            DueStatus::Later => None,
+        }
+    }
+}
+
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    if s == "Z" || s == "UTC" {
+        return FixedOffset::east_opt(0);
+    }
+    ["%:z", "%z"].iter().find_map(|format| {
+        DateTime::parse_from_str(
+            &format!("2000-01-01 00:00 {s}"),
+            &format!("%Y-%m-%d %H:%M {format}"),
+        )
+        .ok()
+        .map(|datetime| *datetime.offset())
+    })
+}
+
+impl DueDate {
+    /// The moment after which the todo is overdue
+    pub fn deadline(&self) -> DateTime<Local> {
+        let time = self
+            .time
+            .unwrap_or(NaiveTime::from_hms_opt(23, 59, 59).unwrap_or_default());
+        let naive = NaiveDateTime::new(self.date, time);
+        let datetime = match self.offset {
+            Some(offset) => offset
+                .from_local_datetime(&naive)
+                .single()
+                .map(|datetime| datetime.with_timezone(&Local)),
+            None => Local.from_local_datetime(&naive).earliest(),
+        };
+        // Only a time skipped by a DST change has no local equivalent
+        datetime.unwrap_or_else(|| Local.from_utc_datetime(&naive))
+    }
+
+    /// Parses what the user typed, either an absolute date or a relative one like `tomorrow`
+    pub fn parse_input(input: &str, today: NaiveDate) -> Result<DueDate, String> {
+        input.parse().or_else(|_| {
+            natural::parse_natural(input, today).ok_or(format!(
+                "'{}' isn't a valid date, try YYYY-MM-DD [HH:MM], 'tomorrow', 'next fri 14:00' or 'in 3 days'",
+                input.trim()
+            ))
+        })
+    }
+
+    pub fn status(&self, now: DateTime<Local>) -> DueStatus {
+        let deadline = self.deadline();
+        if deadline < now {
+            DueStatus::Overdue
+        } else if deadline.date_naive() == now.date_naive() {
+            DueStatus::Today
+        } else if deadline - now < TimeDelta::days(DUE_SOON_DAYS) {
+            DueStatus::Soon
+        } else {
+            DueStatus::Later
+        }
+    }
+}
+
+/// Converts a due date from the time they were free-form strings, those that can't be parsed
+/// being moved to the description so that nothing is lost
+pub fn migrate_due_date_string(due_date: &str, description: &mut String) -> Option<DueDate> {
+    if due_date.trim().is_empty() {
+        return None;
+    }
+    match due_date.parse() {
+        Ok(due_date) => Some(due_date),
+        Err(_) => {
+            if !description.is_empty() {
+                description.push_str("\n\n");
+            }
+            description.push_str(&format!("Due date: {due_date}"));
+            None
+        }
+    }
+}
+
+impl fmt::Display for DueDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.date.format("%Y-%m-%d"))?;
+        if let Some(time) = self.time {
+            write!(f, " {}", time.format("%H:%M"))?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DueDate {
+    type Err = String;
+
+    /// Parses dates such as `2024-12-31`, `2024-12-31 18:30` or `2024-12-31T18:30+01:00`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("'{s}' isn't a valid date, expected YYYY-MM-DD [HH:MM] [+HH:MM]");
+        let normalized = s.trim().replacen('T', " ", 1);
+        let mut parts = normalized.split_whitespace();
+
+        let date_part = parts.next().ok_or_else(error)?;
+        let date = DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(date_part, format).ok())
+            .ok_or_else(error)?;
+
+        let mut time = None;
+        let mut offset = None;
+        if let Some(mut time_part) = parts.next() {
+            // The offset can be glued to the time, as in RFC 3339
+            if let Some(idx) = time_part.find(['+', '-', 'Z']) {
+                offset = Some(parse_offset(&time_part[idx..]).ok_or_else(error)?);
+                time_part = &time_part[..idx];
+            }
+            time = Some(
+                TIME_FORMATS
+                    .iter()
+                    .find_map(|format| NaiveTime::parse_from_str(time_part, format).ok())
+                    .ok_or_else(error)?,
+            );
+        }
+        if let Some(offset_part) = parts.next() {
+            if offset.is_some() || time.is_none() {
+                return Err(error());
+            }
+            offset = Some(parse_offset(offset_part).ok_or_else(error)?);
+        }
+        if parts.next().is_some() {
+            return Err(error());
+        }
+
+        Ok(DueDate { date, time, offset })
+    }
+}
+
+impl Serialize for DueDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DueDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn time(h: u32, m: u32) -> Option<NaiveTime> {
+        NaiveTime::from_hms_opt(h, m, 0)
+    }
+
+    /// A wednesday, at noon
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 12, 18, 12, 0, 0).unwrap()
+    }
+
+    fn status(due_date: &str) -> DueStatus {
+        due_date.parse::<DueDate>().unwrap().status(now())
+    }
+
+    #[test]
+    fn dates_are_parsed_with_an_optional_time_and_offset() {
+        let due_date: DueDate = "2024-12-31".parse().unwrap();
+        assert_eq!(due_date.date, date(2024, 12, 31));
+        assert!(due_date.time.is_none() && due_date.offset.is_none());
+
+        let due_date: DueDate = "2024-12-31 18:30".parse().unwrap();
+        assert_eq!(due_date.time, time(18, 30));
+        assert!(due_date.offset.is_none());
+
+        let due_date: DueDate = "2024-12-31T18:30+01:00".parse().unwrap();
+        assert_eq!(due_date.time, time(18, 30));
+        assert_eq!(due_date.offset, FixedOffset::east_opt(3600));
+        assert_eq!(due_date.to_string(), "2024-12-31 18:30 +01:00");
+
+        for input in ["2024/12/31", "31/12/2024", " 2024-12-31 "] {
+            assert_eq!(input.parse::<DueDate>().unwrap().date, date(2024, 12, 31));
+        }
+    }
+
+    #[test]
+    fn invalid_dates_are_refused() {
+        for input in [
+            "",
+            "someday",
+            "2024-13-01",
+            "2024-12-31 25:00",
+            "2024-12-31 +01:00",
+            "2024-12-31 18:30 +01:00 extra",
+        ] {
+            assert!(input.parse::<DueDate>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn dates_are_written_back_as_they_are_parsed() {
+        for input in ["2024-12-31", "2024-12-31 18:30", "2024-12-31 18:30 -05:00"] {
+            assert_eq!(input.parse::<DueDate>().unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn input_can_be_absolute_or_natural() {
+        let today = now().date_naive();
+        let parse = |input| DueDate::parse_input(input, today);
+        assert_eq!(parse("2025-01-05").unwrap().date, date(2025, 1, 5));
+        assert_eq!(parse("tomorrow").unwrap().date, date(2024, 12, 19));
+        let due_date = parse("next fri 14:00").unwrap();
+        assert_eq!(due_date.date, date(2024, 12, 27));
+        assert_eq!(due_date.time, time(14, 0));
+        assert!(parse("whenever")
+            .unwrap_err()
+            .starts_with("'whenever' isn't a valid date"));
+    }
+
+    #[test]
+    fn status_depends_on_the_deadline() {
+        assert!(status("2024-12-17") == DueStatus::Overdue);
+        assert!(status("2024-12-18 09:00") == DueStatus::Overdue);
+        // Without a time, the todo is due at the end of the day
+        assert!(status("2024-12-18") == DueStatus::Today);
+        assert!(status("2024-12-18 18:00") == DueStatus::Today);
+        assert!(status("2024-12-20") == DueStatus::Soon);
+        assert!(status("2024-12-25") == DueStatus::Later);
+    }
+
+    #[test]
+    fn free_form_due_dates_are_migrated_or_kept() {
+        let mut description = String::from("a book");
+        let due_date = migrate_due_date_string("2024-12-31", &mut description);
+        assert_eq!(
+            due_date.map(|due_date| due_date.date),
+            Some(date(2024, 12, 31))
+        );
+        assert!(migrate_due_date_string("someday", &mut description).is_none());
+        assert_eq!(description, "a book\n\nDue date: someday");
+        assert!(migrate_due_date_string(" ", &mut description).is_none());
+    }
+}
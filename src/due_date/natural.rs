@@ -0,0 +1,276 @@
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveTime, Weekday};
+
+use crate::due_date::DueDate;
+
+const WEEKDAYS: &[(&str, Weekday)] = &[
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// Matches full weekday names and their abbreviations of at least 3 letters
+pub(super) fn parse_weekday(word: &str) -> Option<Weekday> {
+    if word.len() < 3 {
+        return None;
+    }
+    WEEKDAYS
+        .iter()
+        .find(|(name, _)| name.starts_with(word))
+        .map(|(_, weekday)| *weekday)
+}
+
+fn parse_unit(word: &str) -> Option<Unit> {
+    match word.trim_end_matches('s') {
+        "d" | "day" => Some(Unit::Day),
+        "w" | "week" => Some(Unit::Week),
+        "m" | "month" => Some(Unit::Month),
+        "y" | "year" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+fn parse_amount(word: &str) -> Option<u32> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        _ => word.parse().ok(),
+    }
+}
+
+/// Parses an amount glued to its unit, as in `3d`
+fn parse_glued_amount(word: &str) -> Option<(u32, Unit)> {
+    let split = word.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = word.split_at(split);
+    Some((amount.parse().ok()?, parse_unit(unit)?))
+}
+
+/// Parses times such as `14:00`, `2pm`, `9:30am` or `noon`
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    if word == "noon" {
+        return NaiveTime::from_hms_opt(12, 0, 0);
+    }
+    let (clock, pm) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(false)),
+        (_, Some(clock)) => (clock, Some(true)),
+        _ => (word, None),
+    };
+    let (hours, minutes) = match clock.split_once(':') {
+        Some((hours, minutes)) => (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?),
+        // A bare number is only a time with am/pm, otherwise it is ambiguous
+        None if pm.is_some() => (clock.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let hours = match pm {
+        Some(pm) if (1..=12).contains(&hours) => hours % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None => hours,
+    };
+    NaiveTime::from_hms_opt(hours, minutes, 0)
+}
+
+fn add(date: NaiveDate, amount: u32, unit: Unit) -> Option<NaiveDate> {
+    match unit {
+        Unit::Day => date.checked_add_days(Days::new(amount.into())),
+        Unit::Week => date.checked_add_days(Days::new(7 * u64::from(amount))),
+        Unit::Month => date.checked_add_months(Months::new(amount)),
+        Unit::Year => date.checked_add_months(Months::new(12 * amount)),
+    }
+}
+
+/// Number of days from the date to the first given weekday on or after it
+fn days_until(date: NaiveDate, weekday: Weekday) -> u64 {
+    ((7 + weekday.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7).into()
+}
+
+/// The first given weekday strictly after the date
+fn next_weekday(date: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let days = days_until(date, weekday);
+    date.checked_add_days(Days::new(if days == 0 { 7 } else { days }))
+}
+
+/// The first given weekday on or after the date
+fn this_weekday(date: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    date.checked_add_days(Days::new(days_until(date, weekday)))
+}
+
+/// The given weekday in the week after the date's, weeks starting on monday
+fn weekday_of_next_week(date: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let next_monday = next_weekday(date, Weekday::Mon)?;
+    this_weekday(next_monday, weekday)
+}
+
+fn end_of(date: NaiveDate, unit: Unit) -> Option<NaiveDate> {
+    match unit {
+        Unit::Day => Some(date),
+        Unit::Week => date.checked_add_days(Days::new(
+            6 - u64::from(date.weekday().num_days_from_monday()),
+        )),
+        Unit::Month => NaiveDate::from_ymd_opt(date.year(), date.month(), 1)?
+            .checked_add_months(Months::new(1))?
+            .pred_opt(),
+        Unit::Year => NaiveDate::from_ymd_opt(date.year(), 12, 31),
+    }
+}
+
+fn parse_date(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    match words {
+        [] | ["today"] | ["tod"] => Some(today),
+        ["tomorrow"] | ["tmr"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["this", word] if parse_weekday(word).is_some() => {
+            this_weekday(today, parse_weekday(word)?)
+        }
+        ["next", word] if parse_weekday(word).is_some() => {
+            weekday_of_next_week(today, parse_weekday(word)?)
+        }
+        [word] if parse_weekday(word).is_some() => next_weekday(today, parse_weekday(word)?),
+        ["next", unit] => add(today, 1, parse_unit(unit)?),
+        ["eow"] => end_of(today, Unit::Week),
+        ["eom"] => end_of(today, Unit::Month),
+        ["eoy"] => end_of(today, Unit::Year),
+        ["in", word] | [word] => {
+            let (amount, unit) = parse_glued_amount(word)?;
+            add(today, amount, unit)
+        }
+        ["in", amount, unit] | [amount, unit] => {
+            add(today, parse_amount(amount)?, parse_unit(unit)?)
+        }
+        ["end", "of", "the", unit] | ["end", "of", unit] => end_of(today, parse_unit(unit)?),
+        _ => None,
+    }
+}
+
+/// Parses relative dates such as `tomorrow`, `next fri 14:00`, `in 3 days` or `end of month`,
+/// relative to `today`
+pub fn parse_natural(input: &str, today: NaiveDate) -> Option<DueDate> {
+    let lowercase = input.trim().to_lowercase();
+    let mut words = Vec::from_iter(lowercase.split_whitespace());
+
+    let mut time = None;
+    if let Some(last) = words.last() {
+        if let Some(parsed) = parse_time(last) {
+            time = Some(parsed);
+            words.pop();
+            if words.last() == Some(&"at") {
+                words.pop();
+            }
+        }
+    }
+    if words.is_empty() && time.is_none() {
+        return None;
+    }
+
+    Some(DueDate {
+        date: parse_date(&words, today)?,
+        time,
+        offset: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// A wednesday
+    fn today() -> NaiveDate {
+        date(2024, 12, 18)
+    }
+
+    fn parse_date_str(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+        parse_natural(input, today).map(|due_date| due_date.date)
+    }
+
+    #[test]
+    fn bare_weekday_is_the_next_one() {
+        assert_eq!(parse_date_str("fri", today()), Some(date(2024, 12, 20)));
+        assert_eq!(parse_date_str("mon", today()), Some(date(2024, 12, 23)));
+        assert_eq!(
+            parse_date_str("wednesday", today()),
+            Some(date(2024, 12, 25))
+        );
+    }
+
+    #[test]
+    fn this_weekday_includes_today() {
+        assert_eq!(parse_date_str("this wed", today()), Some(today()));
+        assert_eq!(
+            parse_date_str("this fri", today()),
+            Some(date(2024, 12, 20))
+        );
+        assert_eq!(
+            parse_date_str("this mon", today()),
+            Some(date(2024, 12, 23))
+        );
+    }
+
+    #[test]
+    fn next_weekday_is_in_the_following_week() {
+        assert_eq!(
+            parse_date_str("next fri", today()),
+            Some(date(2024, 12, 27))
+        );
+        assert_eq!(
+            parse_date_str("next wed", today()),
+            Some(date(2024, 12, 25))
+        );
+        assert_eq!(
+            parse_date_str("next mon", today()),
+            Some(date(2024, 12, 23))
+        );
+        // From a sunday, the following week starts the next day
+        assert_eq!(
+            parse_date_str("next fri", date(2024, 12, 22)),
+            Some(date(2024, 12, 27))
+        );
+    }
+
+    #[test]
+    fn weekday_accepts_a_time() {
+        let due_date = parse_natural("next fri 14:00", today()).unwrap();
+        assert_eq!(due_date.date, date(2024, 12, 27));
+        assert_eq!(due_date.time, NaiveTime::from_hms_opt(14, 0, 0));
+    }
+
+    #[test]
+    fn relative_days_and_units() {
+        assert_eq!(
+            parse_date_str("tomorrow", today()),
+            Some(date(2024, 12, 19))
+        );
+        assert_eq!(
+            parse_date_str("in 3 days", today()),
+            Some(date(2024, 12, 21))
+        );
+        assert_eq!(parse_date_str("2w", today()), Some(date(2025, 1, 1)));
+        assert_eq!(
+            parse_date_str("next month", today()),
+            Some(date(2025, 1, 18))
+        );
+        assert_eq!(parse_date_str("eow", today()), Some(date(2024, 12, 22)));
+        assert_eq!(
+            parse_date_str("end of month", today()),
+            Some(date(2024, 12, 31))
+        );
+    }
+
+    #[test]
+    fn unknown_words_are_not_dates() {
+        assert_eq!(parse_date_str("fr", today()), None);
+        assert_eq!(parse_date_str("this week", today()), None);
+        assert_eq!(parse_date_str("someday", today()), None);
+    }
+}
@@ -0,0 +1,326 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+use crate::due_date::{natural::parse_weekday, DueDate};
+
+/// How a todo comes back once it is done
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Recurrence {
+    Daily,
+    /// On the given weekdays, on the weekday of the due date when none are given
+    Weekly(Vec<Weekday>),
+    /// On the given day of the month, on the day of the due date when none is given. Months
+    /// too short for it use their last day instead. The day is set when the todo gets the rule,
+    /// so that it doesn't drift to the last day of a shorter month
+    Monthly(Option<u32>),
+    /// A number of days after the todo was done, whenever it was due
+    AfterCompletion(u32),
+}
+
+/// The day of the month, or the last day of the month if it is too short
+fn day_of_month(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let last = first.checked_add_months(Months::new(1))?.pred_opt()?;
+    first.with_day(day.min(last.day()))
+}
+
+fn parse_day_of_month(word: &str) -> Option<u32> {
+    let day = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    match day.parse() {
+        Ok(day) if (1..=31).contains(&day) => Some(day),
+        _ => None,
+    }
+}
+
+impl Recurrence {
+    /// The first occurrence strictly after `date`, `anchor` being the date the occurrences
+    /// line up on when the rule doesn't say
+    fn next_after(&self, date: NaiveDate, anchor: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Daily => date.succ_opt(),
+            Recurrence::Weekly(weekdays) => (1..=7)
+                .filter_map(|days| date.checked_add_days(Days::new(days)))
+                .find(|next| match weekdays.is_empty() {
+                    true => next.weekday() == anchor.weekday(),
+                    false => weekdays.contains(&next.weekday()),
+                }),
+            Recurrence::Monthly(day) => {
+                let day = day.unwrap_or(anchor.day());
+                let this_month = day_of_month(date.year(), date.month(), day)?;
+                match this_month > date {
+                    true => Some(this_month),
+                    false => {
+                        let next_month = date.with_day(1)?.checked_add_months(Months::new(1))?;
+                        day_of_month(next_month.year(), next_month.month(), day)
+                    }
+                }
+            }
+            Recurrence::AfterCompletion(days) => date.checked_add_days(Days::new((*days).into())),
+        }
+    }
+
+    /// Due date of the next occurrence of a todo done on `completed`, skipping the occurrences
+    /// that were missed. The time of the due date is kept
+    pub fn next_due(&self, due_date: Option<DueDate>, completed: NaiveDate) -> Option<DueDate> {
+        let anchor = due_date.map_or(completed, |due_date| due_date.date);
+        let date = match self {
+            Recurrence::AfterCompletion(_) => self.next_after(completed, anchor)?,
+            _ => {
+                let mut date = self.next_after(anchor, anchor)?;
+                while date <= completed {
+                    date = self.next_after(date, anchor)?;
+                }
+                date
+            }
+        };
+        Some(match due_date {
+            Some(due_date) => DueDate { date, ..due_date },
+            None => DueDate {
+                date,
+                time: None,
+                offset: None,
+            },
+        })
+    }
+
+    /// The rule with the day of the month it repeats on set, from the due date or from `today`
+    /// for a todo that isn't due
+    pub fn pinned(self, due_date: Option<DueDate>, today: NaiveDate) -> Recurrence {
+        match self {
+            Recurrence::Monthly(None) => {
+                Recurrence::Monthly(Some(due_date.map_or(today, |due_date| due_date.date).day()))
+            }
+            recurrence => recurrence,
+        }
+    }
+
+    /// Parses what the user typed, an empty input meaning the todo doesn't repeat
+    pub fn parse_input(input: &str) -> Result<Option<Recurrence>, String> {
+        match input.trim().is_empty() {
+            true => Ok(None),
+            false => input.parse().map(Some),
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(weekdays) => {
+                write!(f, "weekly")?;
+                for weekday in weekdays {
+                    write!(f, " {}", weekday.to_string().to_lowercase())?;
+                }
+                Ok(())
+            }
+            Recurrence::Monthly(None) => write!(f, "monthly"),
+            Recurrence::Monthly(Some(day)) => write!(f, "monthly {day}"),
+            Recurrence::AfterCompletion(1) => write!(f, "every 1 day"),
+            Recurrence::AfterCompletion(days) => write!(f, "every {days} days"),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    /// Parses rules such as `daily`, `weekly mon thu`, `monthly 15` or `every 3 days`, the
+    /// latter counting from when the todo is done
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "'{}' isn't a valid recurrence, try 'daily', 'weekly mon thu', 'monthly 15' or 'every 3 days'",
+                s.trim()
+            )
+        };
+        let lowercase = s.trim().to_lowercase();
+        let words = Vec::from_iter(lowercase.split([' ', ',']).filter(|w| !w.is_empty()));
+
+        match words.as_slice() {
+            ["daily"] | ["every", "day"] => Ok(Recurrence::Daily),
+            ["weekly", "on", weekdays @ ..] | ["weekly", weekdays @ ..] => {
+                let weekdays = weekdays
+                    .iter()
+                    .map(|word| parse_weekday(word))
+                    .collect::<Option<Vec<Weekday>>>()
+                    .ok_or_else(error)?;
+                Ok(Recurrence::Weekly(weekdays))
+            }
+            ["monthly"] => Ok(Recurrence::Monthly(None)),
+            ["monthly", "on", day] | ["monthly", day] => Ok(Recurrence::Monthly(Some(
+                parse_day_of_month(day).ok_or_else(error)?,
+            ))),
+            ["every", days, unit] | ["every", days, unit, "after", "done" | "completion"]
+                if unit.trim_end_matches('s') == "day" =>
+            {
+                match days.parse() {
+                    Ok(days) if days > 0 => Ok(Recurrence::AfterCompletion(days)),
+                    _ => Err(error()),
+                }
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+impl Serialize for Recurrence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn due(date: NaiveDate) -> Option<DueDate> {
+        Some(DueDate {
+            date,
+            time: None,
+            offset: None,
+        })
+    }
+
+    /// Date of the next occurrence of a todo due on `due_date` and done on `completed`
+    fn next(recurrence: &Recurrence, due_date: NaiveDate, completed: NaiveDate) -> NaiveDate {
+        recurrence.next_due(due(due_date), completed).unwrap().date
+    }
+
+    #[test]
+    fn monthly_is_clamped_to_the_end_of_shorter_months() {
+        let recurrence = Recurrence::Monthly(None).pinned(due(date(2025, 1, 31)), date(2025, 1, 1));
+        assert_eq!(recurrence, Recurrence::Monthly(Some(31)));
+        let february = next(&recurrence, date(2025, 1, 31), date(2025, 1, 31));
+        assert_eq!(february, date(2025, 2, 28));
+        // The day doesn't drift to the 28th after February
+        assert_eq!(next(&recurrence, february, february), date(2025, 3, 31));
+        assert_eq!(
+            next(&recurrence, date(2024, 1, 31), date(2024, 1, 31)),
+            date(2024, 2, 29)
+        );
+    }
+
+    #[test]
+    fn monthly_without_a_due_date_is_pinned_to_today() {
+        let recurrence = Recurrence::Monthly(None).pinned(None, date(2025, 3, 12));
+        assert_eq!(recurrence, Recurrence::Monthly(Some(12)));
+        assert_eq!(recurrence.to_string(), "monthly 12");
+    }
+
+    #[test]
+    fn weekly_without_weekdays_repeats_on_the_due_weekday() {
+        let recurrence: Recurrence = "weekly".parse().unwrap();
+        assert_eq!(recurrence, Recurrence::Weekly(Vec::new()));
+        // A thursday
+        assert_eq!(
+            next(&recurrence, date(2025, 1, 2), date(2025, 1, 2)),
+            date(2025, 1, 9)
+        );
+        assert_eq!(
+            next(&recurrence, date(2025, 1, 2), date(2024, 12, 30)),
+            date(2025, 1, 9)
+        );
+    }
+
+    #[test]
+    fn weekly_with_weekdays_goes_to_the_next_one() {
+        let recurrence: Recurrence = "weekly mon thu".parse().unwrap();
+        // From a monday to the thursday, then to the next monday
+        assert_eq!(
+            next(&recurrence, date(2025, 1, 6), date(2025, 1, 6)),
+            date(2025, 1, 9)
+        );
+        assert_eq!(
+            next(&recurrence, date(2025, 1, 9), date(2025, 1, 9)),
+            date(2025, 1, 13)
+        );
+    }
+
+    #[test]
+    fn after_completion_counts_from_when_the_todo_is_done() {
+        let recurrence: Recurrence = "every 3 days".parse().unwrap();
+        assert_eq!(recurrence, Recurrence::AfterCompletion(3));
+        assert_eq!(
+            next(&recurrence, date(2025, 1, 1), date(2025, 1, 10)),
+            date(2025, 1, 13)
+        );
+        assert_eq!(
+            next(&recurrence, date(2025, 1, 10), date(2025, 1, 2)),
+            date(2025, 1, 5)
+        );
+        // Without a due date too
+        assert_eq!(
+            recurrence.next_due(None, date(2025, 1, 10)).unwrap().date,
+            date(2025, 1, 13)
+        );
+    }
+
+    #[test]
+    fn missed_occurrences_are_caught_up() {
+        // Done two weeks and a half late, the two missed occurrences are skipped
+        assert_eq!(
+            next(
+                &Recurrence::Weekly(Vec::new()),
+                date(2025, 1, 2),
+                date(2025, 1, 19)
+            ),
+            date(2025, 1, 23)
+        );
+        assert_eq!(
+            next(&Recurrence::Daily, date(2025, 1, 1), date(2025, 1, 10)),
+            date(2025, 1, 11)
+        );
+        assert_eq!(
+            next(
+                &Recurrence::Monthly(Some(31)),
+                date(2025, 1, 31),
+                date(2025, 4, 2)
+            ),
+            date(2025, 4, 30)
+        );
+    }
+
+    #[test]
+    fn the_time_of_the_due_date_is_kept() {
+        let due_date = DueDate {
+            date: date(2025, 1, 1),
+            time: NaiveTime::from_hms_opt(9, 30, 0),
+            offset: None,
+        };
+        let next = Recurrence::Daily
+            .next_due(Some(due_date), date(2025, 1, 1))
+            .unwrap();
+        assert_eq!(next.to_string(), "2025-01-02 09:30");
+    }
+
+    #[test]
+    fn rules_are_written_back_as_they_are_parsed() {
+        for rule in [
+            "daily",
+            "weekly mon thu",
+            "monthly 15",
+            "every 1 day",
+            "every 3 days",
+        ] {
+            assert_eq!(rule.parse::<Recurrence>().unwrap().to_string(), rule);
+        }
+        assert!("monthly 32".parse::<Recurrence>().is_err());
+        assert!("every 0 days".parse::<Recurrence>().is_err());
+        assert!("weekly someday".parse::<Recurrence>().is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use uuid::Uuid;
+
+use crate::{store::json::write_atomically, todo::Todo, trash::TrashEntry};
+
+/// Commands older than this are forgotten
+const MAX_COMMANDS: usize = 100;
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// A change to one of the top-level todos, to the trash or to the archive, along with what it
+/// replaced so that it can be reverted
+#[derive(Serialize, Deserialize, Clone)]
+pub enum Step {
+    Added {
+        idx: usize,
+        todo: Todo,
+    },
+    Edited {
+        idx: usize,
+        before: Box<Todo>,
+        after: Box<Todo>,
+    },
+    Deleted {
+        idx: usize,
+        todo: Todo,
+    },
+    /// The todo was put in the trash at `idx`
+    Trashed {
+        idx: usize,
+        entry: Box<TrashEntry>,
+    },
+    Untrashed {
+        idx: usize,
+        entry: Box<TrashEntry>,
+    },
+    /// The todo was put in the archive at `idx`
+    Archived {
+        idx: usize,
+        todo: Box<Todo>,
+    },
+    Unarchived {
+        idx: usize,
+        todo: Box<Todo>,
+    },
+}
+
+/// Whether the todo or one of its subtasks is one of the given todos
+fn contains(todo: &Todo, ids: &[Uuid]) -> bool {
+    ids.contains(&todo.id) || todo.children.iter().any(|child| contains(child, ids))
+}
+
+impl Step {
+    /// Whether one of the given todos is part of the step, as a subtask too
+    fn involves(&self, ids: &[Uuid]) -> bool {
+        match self {
+            Step::Added { todo, .. } | Step::Deleted { todo, .. } => contains(todo, ids),
+            Step::Edited { before, after, .. } => contains(before, ids) || contains(after, ids),
+            Step::Trashed { entry, .. } | Step::Untrashed { entry, .. } => {
+                contains(&entry.todo, ids)
+            }
+            Step::Archived { todo, .. } | Step::Unarchived { todo, .. } => contains(todo, ids),
+        }
+    }
+
+    /// Whether the step changes the trash or the archive, which are stored apart from the todos
+    pub fn is_stored_apart(&self) -> bool {
+        matches!(
+            self,
+            Step::Trashed { .. }
+                | Step::Untrashed { .. }
+                | Step::Archived { .. }
+                | Step::Unarchived { .. }
+        )
+    }
+
+    /// The step that reverts this one
+    pub fn inverse(self) -> Step {
+        match self {
+            Step::Added { idx, todo } => Step::Deleted { idx, todo },
+            Step::Edited { idx, before, after } => Step::Edited {
+                idx,
+                before: after,
+                after: before,
+            },
+            Step::Deleted { idx, todo } => Step::Added { idx, todo },
+            Step::Trashed { idx, entry } => Step::Untrashed { idx, entry },
+            Step::Untrashed { idx, entry } => Step::Trashed { idx, entry },
+            Step::Archived { idx, todo } => Step::Unarchived { idx, todo },
+            Step::Unarchived { idx, todo } => Step::Archived { idx, todo },
+        }
+    }
+}
+
+/// What a single action of the user did, undone and redone at once
+#[derive(Serialize, Deserialize)]
+pub struct Command {
+    /// Describes the action, such as "delete 'milk'"
+    pub label: String,
+    pub steps: Vec<Step>,
+}
+
+impl Command {
+    pub fn new(label: String) -> Self {
+        Command {
+            label,
+            steps: Vec::new(),
+        }
+    }
+}
+
+/// The commands that can be undone, the most recent last, and those that were undone and can be
+/// redone
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    /// Identifies the todos the history was saved along with, as it only applies to them
+    #[serde(default)]
+    fingerprint: u64,
+}
+
+/// 64-bit FNV-1a hash, which unlike the standard library's hasher is the same in every build
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Hash of the todos, which changes whenever one of them does
+pub fn fingerprint(todos: &[Todo]) -> u64 {
+    fnv1a(serde_json::to_string(todos).unwrap_or_default().as_bytes())
+}
+
+impl History {
+    /// A new action can't be redone on top of, so the undone commands are dropped
+    pub fn push(&mut self, command: Command) {
+        if command.steps.is_empty() {
+            return;
+        }
+        self.undo.push(command);
+        if self.undo.len() > MAX_COMMANDS {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Command> {
+        self.undo.pop()
+    }
+
+    pub fn push_undo(&mut self, command: Command) {
+        self.undo.push(command);
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Command> {
+        self.redo.pop()
+    }
+
+    pub fn push_redo(&mut self, command: Command) {
+        self.redo.push(command);
+    }
+
+    /// Forgets the commands involving the todos, which were deleted for good. The commands
+    /// undone before them or redone after them are forgotten too, their steps relying on the
+    /// positions the forgotten ones left the todos in
+    pub fn forget(&mut self, ids: &[Uuid]) {
+        let involves = |command: &Command| command.steps.iter().any(|step| step.involves(ids));
+        if let Some(idx) = self.undo.iter().rposition(involves) {
+            self.undo.drain(..=idx);
+        }
+        if let Some(idx) = self.redo.iter().rposition(involves) {
+            self.redo.drain(..=idx);
+        }
+    }
+
+    /// Reads the history saved at the end of the last session, which is only kept if the todos
+    /// haven't changed since. Nothing can be undone when it can't be read
+    pub fn load(file_path: &str, todos: &[Todo]) -> History {
+        fs::read_to_string(file_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<History>(&contents).ok())
+            .filter(|history| history.fingerprint == fingerprint(todos))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&mut self, file_path: &str, todos: &[Todo]) -> Result<(), String> {
+        self.fingerprint = fingerprint(todos);
+        let contents = serde_json::to_string(self).map_err(|error| error.to_string())?;
+        write_atomically(file_path, contents.as_bytes()).map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_the_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        // Saved histories are matched against this value, it must never change
+        assert_eq!(fingerprint(&[]), 0x0961_2b07_b5ec_b5a5);
+    }
+}
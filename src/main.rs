@@ -1,15 +1,35 @@
+use archive::Archive;
 use homedir::my_home;
-use std::fs::{exists, read_to_string};
+use std::{env, process::Command};
 
 pub mod tui;
 use tui::{drawing::draw, events_handling::handle_events};
 
+mod archive;
+mod due_date;
+mod history;
+mod priority;
+mod search;
+mod settings;
 mod states;
+mod status;
+mod store;
+mod tags;
+mod text_input;
 mod todo;
-use states::States;
+mod trash;
+use settings::Settings;
+use states::{Screens, States};
+use status::Statuses;
+#[cfg(not(feature = "sqlite"))]
+use store::json::JsonStore;
+#[cfg(feature = "sqlite")]
+use store::sqlite::SqliteStore;
+use store::TodoStore;
 use todo::Todos;
+use trash::Trash;
 
-fn get_file_path(states: &mut States) -> Option<String> {
+fn get_file_path(states: &mut States, file_name: &str) -> Option<String> {
     let home_dir = match my_home() {
         Ok(home_dir) => home_dir,
         Err(error) => panic!("Couldn't get the home dir: {error:?}"),
@@ -21,7 +41,7 @@ fn get_file_path(states: &mut States) -> Option<String> {
     };
     match home_dir_str.to_str() {
         Some(s) => {
-            let file_path = String::from(s) + "/.config/todo-tui/todos.json";
+            let file_path = String::from(s) + "/.config/todo-tui/" + file_name;
             states.set_file_path(file_path.clone());
             Some(file_path)
         }
@@ -29,26 +49,130 @@ fn get_file_path(states: &mut States) -> Option<String> {
     }
 }
 
+/// Opens the file in the user's editor, the TUI having to be suspended while it runs
+fn open_in_editor(file_path: &str) {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or(String::from("vi"));
+    if Command::new(&editor).arg(file_path).status().is_err() {
+        println!("couldn't run the editor '{editor}'");
+    }
+}
+
 fn main() {
     let mut states = States::new();
 
-    let file_path = match get_file_path(&mut states) {
-        Some(file_path) => file_path,
+    let statuses_path = match get_file_path(&mut states, "statuses.json") {
+        Some(statuses_path) => statuses_path,
         None => return,
     };
+    let statuses = match Statuses::load(&statuses_path) {
+        Ok(statuses) => statuses,
+        Err(error) => panic!(
+            "Couldn't read the statuses from '{}': {error}",
+            statuses_path
+        ),
+    };
 
-    let file_exists = exists(&file_path);
-    if (file_exists.is_err() || file_exists.is_ok_and(|v| !v))
-        && std::fs::File::create(&file_path).is_err()
-    {
-        panic!("The file todos file does not exist and could not be create")
+    let settings_path = match get_file_path(&mut states, "settings.json") {
+        Some(settings_path) => settings_path,
+        None => return,
+    };
+    // Settings that can't be read are neither used nor overwritten, so that they can be fixed
+    let (mut settings, settings_error) = match Settings::load(&settings_path) {
+        Ok(settings) => (settings, None),
+        Err(error) => (
+            Settings::default(),
+            Some(format!(
+                "couldn't read the settings from '{settings_path}', the defaults are used: {error}"
+            )),
+        ),
     };
-    let file_contents = match read_to_string(&file_path) {
-        Ok(file_contents) => file_contents,
-        Err(_) => panic!("Counld read the file '{}'", file_path),
+    let history_path = match get_file_path(&mut states, "history.json") {
+        Some(history_path) => history_path,
+        None => return,
     };
 
-    let mut todos = Todos::new(file_contents, file_path);
+    let archive_path = match get_file_path(&mut states, "archive.json") {
+        Some(archive_path) => archive_path,
+        None => return,
+    };
+    #[cfg(not(feature = "sqlite"))]
+    let archive_store: Box<dyn TodoStore> =
+        Box::new(JsonStore::new(archive_path.clone(), statuses.clone()));
+    #[cfg(feature = "sqlite")]
+    let archive_store: Box<dyn TodoStore> = {
+        let archive_db_path = match get_file_path(&mut states, "archive.db") {
+            Some(archive_db_path) => archive_db_path,
+            None => return,
+        };
+        match SqliteStore::open(
+            archive_db_path.clone(),
+            archive_path.clone(),
+            statuses.clone(),
+        ) {
+            Ok(store) => Box::new(store),
+            Err(error) => panic!("Couldn't open the database '{}': {error}", archive_db_path),
+        }
+    };
+    let archive = match Archive::open(archive_store) {
+        Ok(archive) => archive,
+        Err(error) => panic!("Couldn't read the archive from '{}': {error}", archive_path),
+    };
+
+    let trash_path = match get_file_path(&mut states, "trash.json") {
+        Some(trash_path) => trash_path,
+        None => return,
+    };
+    let trash = match Trash::load(trash_path.clone()) {
+        Ok(trash) => trash,
+        Err(error) => panic!("Couldn't read the trash from '{}': {error}", trash_path),
+    };
+
+    let file_path = match get_file_path(&mut states, "todos.json") {
+        Some(file_path) => file_path,
+        None => return,
+    };
+
+    #[cfg(not(feature = "sqlite"))]
+    let store: Box<dyn TodoStore> = Box::new(JsonStore::new(file_path, statuses.clone()));
+    #[cfg(feature = "sqlite")]
+    let store: Box<dyn TodoStore> = {
+        let db_path = match get_file_path(&mut states, "todos.db") {
+            Some(db_path) => db_path,
+            None => return,
+        };
+        match SqliteStore::open(db_path.clone(), file_path, statuses.clone()) {
+            Ok(store) => Box::new(store),
+            Err(error) => panic!("Couldn't open the database '{}': {error}", db_path),
+        }
+    };
+
+    let location = store.location();
+    let mut todos = match Todos::new(store, statuses) {
+        Ok(todos) => todos,
+        Err(error) => panic!("Couldn't read the todos from '{}': {error}", location),
+    };
+    if todos.get_load_error().is_some() {
+        states.set_screen(Screens::Recovery);
+    }
+    todos.set_sort(settings.sort);
+    todos.set_trash(trash);
+    if let Some(reason) = archive.get_read_only_reason() {
+        states.set_error_message(reason.clone());
+    }
+    if let Some(error) = &settings_error {
+        states.set_error_message(error.clone());
+    }
+    todos.set_archive(archive);
+    states.set_archive_days(settings.archive_days);
+    todos.load_history(history_path.clone());
+    // Purging with the default retention could delete todos the settings meant to keep
+    if settings_error.is_none() {
+        if let Err(error) = todos.purge_trash_older_than(settings.trash_days) {
+            states.set_error_message(error);
+        }
+    }
 
     // TUI
     let mut terminal = ratatui::init();
@@ -59,6 +183,26 @@ fn main() {
         if matches!(handle_events(&mut todos, &mut states), Ok(true)) {
             break;
         }
+
+        if states.take_editor_request() {
+            ratatui::restore();
+            open_in_editor(&todos.get_location());
+            terminal = ratatui::init();
+            if todos.reload().is_ok() && todos.get_load_error().is_none() {
+                states.select_first(&mut todos);
+                states.set_screen(Screens::Main);
+            }
+        }
     }
     ratatui::restore();
+
+    settings.sort = todos.get_sort();
+    if settings_error.is_none() {
+        if let Err(error) = settings.save(&settings_path) {
+            println!("couldn't save the settings to '{settings_path}': {error}");
+        }
+    }
+    if let Err(error) = todos.save_history() {
+        println!("couldn't save the history to '{history_path}': {error}");
+    }
 }
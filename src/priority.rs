@@ -0,0 +1,72 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+const PRIORITIES: &[Priority] = &[
+    Priority::None,
+    Priority::Low,
+    Priority::Medium,
+    Priority::High,
+    Priority::Urgent,
+];
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::None => "none",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Priority> {
+        PRIORITIES.iter().find(|p| p.as_str() == s).copied()
+    }
+
+    /// Symbol shown before the title of the todos, todos without priority having none
+    pub fn marker(&self) -> Option<&'static str> {
+        match self {
+            Priority::None => None,
+            Priority::Low => Some("↓"),
+            Priority::Medium => Some("•"),
+            Priority::High => Some("↑"),
+            Priority::Urgent => Some("‼"),
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Priority::None => Color::Gray,
+            Priority::Low => Color::Blue,
+            Priority::Medium => Color::Green,
+            Priority::High => Color::Yellow,
+            Priority::Urgent => Color::LightRed,
+        }
+    }
+
+    pub fn next(&self) -> Priority {
+        match self {
+            Priority::Urgent => Priority::Urgent,
+            _ => PRIORITIES[*self as usize + 1],
+        }
+    }
+
+    pub fn previous(&self) -> Priority {
+        match self {
+            Priority::None => Priority::None,
+            _ => PRIORITIES[*self as usize - 1],
+        }
+    }
+}
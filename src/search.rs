@@ -0,0 +1,268 @@
+use ratatui::{
+    style::{Color, Style},
+    text::Span,
+};
+use std::ops::Range;
+use uuid::Uuid;
+
+use crate::todo::Todo;
+
+const HIGHLIGHT_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+
+/// Byte ranges of the text where the query is found, whatever the case
+fn find_all(text: &str, query: &str) -> Vec<Range<usize>> {
+    let text_lowercase = text.to_lowercase();
+    let query = query.to_lowercase();
+    // Lowercasing can change the length of some characters, whose positions wouldn't match
+    if query.is_empty() || text_lowercase.len() != text.len() {
+        return Vec::new();
+    }
+    Vec::from_iter(
+        text_lowercase
+            .match_indices(&query)
+            .map(|(start, found)| start..start + found.len())
+            .filter(|range| text.is_char_boundary(range.start) && text.is_char_boundary(range.end)),
+    )
+}
+
+fn contains(text: &str, query: &str) -> bool {
+    text.to_lowercase().contains(&query.to_lowercase())
+}
+
+/// Whether the title, the description or one of the tags of the todo contains the query,
+/// whatever the case
+pub fn matches(todo: &Todo, query: &str) -> bool {
+    contains(&todo.title, query)
+        || contains(&todo.description, query)
+        || todo.tags.iter().any(|tag| contains(tag, query))
+}
+
+/// Splits the text into spans of the given style, the parts matching the query being highlighted
+pub fn highlight(text: &str, query: &str, style: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for range in find_all(text, query) {
+        if range.start > end {
+            spans.push(Span::styled(String::from(&text[end..range.start]), style));
+        }
+        spans.push(Span::styled(
+            String::from(&text[range.clone()]),
+            style.patch(HIGHLIGHT_STYLE),
+        ));
+        end = range.end;
+    }
+    if end < text.len() || spans.is_empty() {
+        spans.push(Span::styled(String::from(&text[end..]), style));
+    }
+    spans
+}
+
+const SCORE_MATCH: i64 = 16;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+/// Matching the start of a word, as typing initials is common
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL_CASE: i64 = 7;
+/// The least a character following a match gets, so that contiguous matches stay together
+const BONUS_CONSECUTIVE: i64 = 4;
+const FIRST_CHAR_MULTIPLIER: i64 = 2;
+const MIN_SCORE: i64 = i64::MIN / 2;
+
+/// Where the query was found in a text and how well it matches
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Indices of the characters of the text matching those of the query
+    pub positions: Vec<usize>,
+}
+
+fn char_bonus(previous: Option<char>, current: char) -> i64 {
+    match previous {
+        None => BONUS_BOUNDARY,
+        Some(previous) if !previous.is_alphanumeric() => BONUS_BOUNDARY,
+        Some(previous) if previous.is_lowercase() && current.is_uppercase() => BONUS_CAMEL_CASE,
+        _ => 0,
+    }
+}
+
+fn chars_match(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Finds the characters of the query in the text, in order but not necessarily next to each
+/// other, choosing the alignment that scores best. Matches get bonuses when they start words or
+/// follow each other, and gaps between them are penalized, as in the Smith-Waterman algorithm
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query = Vec::from_iter(query.chars().filter(|c| !c.is_whitespace()));
+    let text = Vec::from_iter(text.chars());
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let mut remaining = query.iter().peekable();
+    for &c in &text {
+        if remaining.peek().is_some_and(|&&q| chars_match(q, c)) {
+            remaining.next();
+        }
+    }
+    if remaining.peek().is_some() {
+        return None;
+    }
+
+    let bonuses = Vec::from_iter(
+        (0..text.len()).map(|j| char_bonus(j.checked_sub(1).map(|p| text[p]), text[j])),
+    );
+    // `matched[i][j]`: best score of the query up to `i`, its character `i` matching `text[j]`.
+    // `gapped[i][j]`: best score of the query up to `i`, its character `i` matching before
+    // `j - 1`, the characters in between being skipped
+    let mut matched = vec![vec![MIN_SCORE; text.len()]; query.len()];
+    let mut gapped = vec![vec![MIN_SCORE; text.len()]; query.len()];
+    for (i, &q) in query.iter().enumerate() {
+        for j in 0..text.len() {
+            if j >= 2 {
+                gapped[i][j] = (matched[i][j - 2] - PENALTY_GAP_START)
+                    .max(gapped[i][j - 1] - PENALTY_GAP_EXTENSION);
+            }
+            if !chars_match(q, text[j]) {
+                continue;
+            }
+            matched[i][j] = match (i, j) {
+                (0, _) => SCORE_MATCH + bonuses[j] * FIRST_CHAR_MULTIPLIER,
+                (_, 0) => MIN_SCORE,
+                _ => (matched[i - 1][j - 1] + bonuses[j].max(BONUS_CONSECUTIVE))
+                    .max(gapped[i - 1][j] + bonuses[j])
+                    .saturating_add(SCORE_MATCH),
+            };
+        }
+    }
+
+    let last = query.len() - 1;
+    let (mut j, &score) = matched[last]
+        .iter()
+        .enumerate()
+        .max_by_key(|&(j, &score)| (score, std::cmp::Reverse(j)))?;
+    if score <= MIN_SCORE / 2 {
+        return None;
+    }
+    // Walks back through the choices that led to the best score
+    let mut positions = vec![j];
+    for i in (0..last).rev() {
+        let consecutive = matched[i][j - 1] + bonuses[j].max(BONUS_CONSECUTIVE) + SCORE_MATCH;
+        j = match consecutive == matched[i + 1][j] {
+            true => j - 1,
+            false => (0..j - 1)
+                .rev()
+                .find(|&k| {
+                    let gap = (j - k - 1) as i64;
+                    matched[i][k] - PENALTY_GAP_START - (gap - 1) * PENALTY_GAP_EXTENSION
+                        == gapped[i][j]
+                })
+                .unwrap_or(j - 1),
+        };
+        positions.push(j);
+    }
+    positions.reverse();
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Spans of the text, the characters at the given positions being highlighted
+pub fn highlight_positions(text: &str, positions: &[usize], style: Style) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    for (idx, c) in text.chars().enumerate() {
+        let c_style = match positions.contains(&idx) {
+            true => style.patch(HIGHLIGHT_STYLE),
+            false => style,
+        };
+        match spans.last_mut() {
+            Some(span) if span.style == c_style => span.content.to_mut().push(c),
+            _ => spans.push(Span::styled(String::from(c), c_style)),
+        }
+    }
+    spans
+}
+
+/// A todo found by the fuzzy finder
+pub struct FuzzyHit {
+    pub id: Uuid,
+    pub score: i64,
+    /// Characters of the title that match, none when it matched on another field
+    pub title_positions: Vec<usize>,
+}
+
+/// Ranks the todos matching the query on any of their fields, the best matches first. The
+/// title counts the most, then the tags and then the description
+pub fn fuzzy_rank(todos: &[&Todo], query: &str) -> Vec<FuzzyHit> {
+    let mut hits = Vec::from_iter(todos.iter().filter_map(|todo| {
+        let title = fuzzy_match(query, &todo.title);
+        let tags = fuzzy_match(query, &todo.tags.join(" ")).map(|found| found.score * 3 / 4);
+        let description = fuzzy_match(query, &todo.description).map(|found| found.score / 2);
+        let score = [title.as_ref().map(|found| found.score), tags, description]
+            .into_iter()
+            .flatten()
+            .max()?;
+        Some(FuzzyHit {
+            id: todo.id,
+            score,
+            title_positions: title.map(|found| found.positions).unwrap_or_default(),
+        })
+    }));
+    // Stable, so todos that score the same stay in the order they are listed in
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.score));
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(query: &str, text: &str) -> Vec<usize> {
+        fuzzy_match(query, text)
+            .map(|found| found.positions)
+            .unwrap_or_default()
+    }
+
+    fn score(query: &str, text: &str) -> i64 {
+        fuzzy_match(query, text).map_or(MIN_SCORE, |found| found.score)
+    }
+
+    #[test]
+    fn consecutive_characters_match_together() {
+        assert_eq!(positions("mil", "buy milk"), [4, 5, 6]);
+        assert_eq!(positions("mil", "mail milk"), [5, 6, 7]);
+    }
+
+    #[test]
+    fn word_starts_are_preferred() {
+        assert_eq!(positions("bm", "ab buy milk"), [3, 7]);
+        assert_eq!(positions("fb", "fooBar"), [0, 3]);
+        assert!(score("bm", "buy milk") > score("bm", "submit"));
+    }
+
+    #[test]
+    fn gaps_are_allowed_but_cost() {
+        assert_eq!(positions("ace", "abcde"), [0, 2, 4]);
+        assert!(score("ace", "ace") > score("ace", "abcde"));
+        assert!(score("ace", "abcde") > score("ace", "abbbbbcdddde"));
+    }
+
+    #[test]
+    fn multibyte_characters_are_counted_as_characters() {
+        assert_eq!(positions("été", "l'été"), [2, 3, 4]);
+        assert_eq!(positions("ÉTÉ", "l'été"), [2, 3, 4]);
+        assert_eq!(positions("日本", "日本語"), [0, 1]);
+    }
+
+    #[test]
+    fn missing_characters_dont_match() {
+        assert!(fuzzy_match("milk", "mil").is_none());
+        assert!(fuzzy_match("lim", "milk").is_none());
+        assert!(fuzzy_match("x", "").is_none());
+    }
+
+    #[test]
+    fn empty_query_matches_anything() {
+        assert_eq!(fuzzy_match("", "milk").map(|found| found.score), Some(0));
+        assert_eq!(positions(" ", "milk"), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{fs, io};
+
+use crate::{store::json::write_atomically, todo::Sort};
+
+fn default_trash_days() -> u64 {
+    30
+}
+
+fn default_archive_days() -> u64 {
+    7
+}
+
+/// Preferences kept from one session to the next
+#[derive(Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub sort: Sort,
+    /// Days after which the deleted todos are purged from the trash, 0 keeping them forever
+    #[serde(default = "default_trash_days")]
+    pub trash_days: u64,
+    /// Age in days of the done todos that can be archived without archiving them all
+    #[serde(default = "default_archive_days")]
+    pub archive_days: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            sort: Sort::default(),
+            trash_days: default_trash_days(),
+            archive_days: default_archive_days(),
+        }
+    }
+}
+
+/// Names the setting that couldn't be read, as serde only tells what was wrong with it
+fn describe_error(value: &Value, error: serde_json::Error) -> String {
+    let field = value.as_object().and_then(|fields| {
+        fields
+            .iter()
+            .find(|(name, field)| Settings::deserialize(&json!({ name.as_str(): field })).is_err())
+    });
+    match field {
+        Some((name, _)) => format!("'{name}': {error}"),
+        None => error.to_string(),
+    }
+}
+
+impl Settings {
+    /// The defaults are used when the file doesn't exist. A file that can't be read is an error,
+    /// naming the field that is wrong, as its settings mustn't be replaced by the defaults
+    pub fn load(file_path: &str) -> Result<Settings, String> {
+        match fs::read_to_string(file_path) {
+            Ok(contents) => {
+                let value: Value =
+                    serde_json::from_str(&contents).map_err(|error| error.to_string())?;
+                Settings::deserialize(&value).map_err(|error| describe_error(&value, error))
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    pub fn save(&self, file_path: &str) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(self).map_err(|error| error.to_string())?;
+        write_atomically(file_path, contents.as_bytes()).map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_contents(contents: &str) -> Result<Settings, String> {
+        let file_path = std::env::temp_dir().join(format!(
+            "todo-tui-settings-{}-{}.json",
+            std::process::id(),
+            contents.len()
+        ));
+        let file_path = file_path.to_string_lossy().into_owned();
+        fs::write(&file_path, contents).unwrap();
+        let settings = Settings::load(&file_path);
+        let _ = fs::remove_file(&file_path);
+        settings
+    }
+
+    #[test]
+    fn missing_fields_get_their_default() {
+        let settings = load_contents(r#"{"trash_days": 90}"#).unwrap();
+        assert_eq!(settings.trash_days, 90);
+        assert_eq!(settings.archive_days, default_archive_days());
+    }
+
+    #[test]
+    fn a_wrong_field_is_named() {
+        let error = load_contents(r#"{"archive_days": 3, "trash_days": "90"}"#)
+            .err()
+            .unwrap();
+        assert!(error.starts_with("'trash_days': "), "{error}");
+    }
+
+    #[test]
+    fn a_missing_file_gives_the_defaults() {
+        let settings = Settings::load("/nonexistent/todo-tui/settings.json").unwrap();
+        assert_eq!(settings.trash_days, default_trash_days());
+    }
+}
@@ -1,10 +1,23 @@
+use chrono::{Days, Local, Months, NaiveDate};
 use ratatui::{style::Color, widgets::ListState};
 
-use crate::todo::Todos;
+use crate::{
+    due_date::{recurrence::Recurrence, DueDate},
+    priority::Priority,
+    search::FuzzyHit,
+    tags::parse_tags,
+    text_input::TextInput,
+    todo::Todos,
+};
+use uuid::Uuid;
 
 pub const MAX_TITLE_LEN: usize = 32;
 pub const MAX_DATE_LEN: usize = 32;
+pub const MAX_TAGS_LEN: usize = 64;
+pub const MAX_RECURRENCE_LEN: usize = 32;
 pub const MAX_DESCRIPTION_LEN: usize = 4096;
+pub const MAX_LIST_NAME_LEN: usize = 24;
+pub const MAX_SEARCH_LEN: usize = 32;
 
 pub const ALL_KEY_EDIT: char = 'e';
 // TODO: Implement copy/paste
@@ -15,37 +28,119 @@ pub enum Screens {
     Main,
     Create,
     Edit,
+    Backups,
+    Recovery,
+    Trash,
+    Archive,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum CreateTab {
     Title,
     Date,
+    Priority,
+    Tags,
+    Recurrence,
     Description,
 }
 
 pub struct States {
     todo_list: ListState,
+    /// Todo the selection is pinned to, its row changing as the list is sorted or filtered
+    selected_todo: Option<Uuid>,
+    backup_list: ListState,
+    trash_list: ListState,
+    archive_list: ListState,
     screen: Screens,
     is_in_writting_mode: bool,
-    title_string: String,
-    date_string: String,
-    description_string: String,
+    title_input: TextInput,
+    date_input: TextInput,
+    description_input: TextInput,
+    tags_input: TextInput,
+    recurrence_input: TextInput,
+    priority: Priority,
     selected_tab: CreateTab,
     file_path: String,
+    /// Day selected in the calendar popup of the create/edit screen, set while it is open
+    calendar: Option<NaiveDate>,
+    /// Shown when the last action couldn't be done, such as saving a todo with an invalid date
+    error_message: Option<String>,
+    /// Tells what the last action did when it can't be seen, such as what was undone
+    notice: Option<String>,
+    /// Asks the main loop to suspend the TUI and open the todos file in an editor
+    editor_requested: bool,
+    /// Todo under which the todo of the create screen is added, if it is a subtask
+    new_todo_parent: Option<Uuid>,
+    /// Parent being completed, waiting for the user to say whether its subtasks are completed too
+    pending_completion: Option<Uuid>,
+    /// Todo waiting for the user to confirm it is deleted
+    pending_deletion: Option<Uuid>,
+    /// Todo of the trash waiting for the user to confirm it is deleted for good
+    pending_purge: Option<Uuid>,
+    /// Whether the user is asked which done todos to archive
+    pending_archive: bool,
+    /// Todos completed at least this many days ago can be archived on their own
+    archive_days: u64,
+    /// Only the archived todos matching it are listed
+    archive_search: String,
+    /// Whether the keys typed go to the search of the archive
+    is_searching_archive: bool,
+    /// Todo waiting for the user to select the todo that blocks it
+    linking: Option<Uuid>,
+    /// Todo being moved, waiting for the user to show the list it goes to
+    moving: Option<Uuid>,
+    /// Name of the list being created, set while it is typed
+    new_list_name: Option<String>,
+    /// Whether the keys typed go to the search, which stays applied once it is no longer typed
+    is_searching: bool,
+    /// Todo selected when the search started, selected again if it is cancelled
+    selected_before_search: Option<Uuid>,
+    /// What is typed in the fuzzy finder, set while it is open
+    finder_query: Option<String>,
+    /// Results of the fuzzy finder, ranked again only when the query changes
+    finder_hits: Vec<FuzzyHit>,
+    /// Row of the fuzzy finder's results that is selected
+    finder_selected: usize,
 }
 
 impl States {
     pub fn new() -> Self {
         let mut ret = Self {
             todo_list: ListState::default(),
+            selected_todo: None,
+            backup_list: ListState::default(),
+            trash_list: ListState::default(),
+            archive_list: ListState::default(),
             screen: Screens::Main,
             is_in_writting_mode: false,
-            title_string: String::new(),
-            date_string: String::new(),
-            description_string: String::new(),
+            title_input: TextInput::new(MAX_TITLE_LEN),
+            date_input: TextInput::new(MAX_DATE_LEN),
+            description_input: TextInput::new(MAX_DESCRIPTION_LEN),
+            tags_input: TextInput::new(MAX_TAGS_LEN),
+            recurrence_input: TextInput::new(MAX_RECURRENCE_LEN),
+            priority: Priority::None,
             selected_tab: CreateTab::Title,
             file_path: String::new(),
+            calendar: None,
+            error_message: None,
+            notice: None,
+            editor_requested: false,
+            new_todo_parent: None,
+            pending_completion: None,
+            pending_deletion: None,
+            pending_purge: None,
+            pending_archive: false,
+            archive_days: 0,
+            archive_search: String::new(),
+            is_searching_archive: false,
+            linking: None,
+            moving: None,
+            new_list_name: None,
+            is_searching: false,
+            selected_before_search: None,
+            finder_query: None,
+            finder_hits: Vec::new(),
+            finder_selected: 0,
         };
         ret.todo_list.select_first();
         ret
@@ -56,6 +151,246 @@ impl States {
         &mut self.todo_list
     }
 
+    pub fn get_backup_list(&mut self) -> &mut ListState {
+        &mut self.backup_list
+    }
+
+    pub fn get_trash_list(&mut self) -> &mut ListState {
+        &mut self.trash_list
+    }
+
+    pub fn get_archive_list(&mut self) -> &mut ListState {
+        &mut self.archive_list
+    }
+
+    pub fn get_selected_id(&mut self) -> Option<Uuid> {
+        self.selected_todo
+    }
+
+    pub fn select_id(&mut self, todos: &mut Todos, id: Uuid) {
+        if let Some(row) = todos.get_row(id) {
+            self.todo_list.select(Some(row));
+            self.selected_todo = Some(id);
+        }
+    }
+
+    pub fn select_row(&mut self, todos: &mut Todos, row: usize) {
+        self.todo_list.select(Some(row));
+        self.selected_todo = todos.get_id(row);
+    }
+
+    pub fn select_first(&mut self, todos: &mut Todos) {
+        self.select_row(todos, 0);
+    }
+
+    /// Moves the selection to the row of the selected todo. When it isn't listed anymore, the
+    /// todo that took its row is selected
+    pub fn sync_selection(&mut self, todos: &mut Todos) {
+        match self.selected_todo.and_then(|id| todos.get_row(id)) {
+            Some(row) => self.todo_list.select(Some(row)),
+            None => match todos.get_nb_rows() {
+                0 => self.selected_todo = None,
+                nb_rows => {
+                    let row = self.todo_list.selected().unwrap_or(0).min(nb_rows - 1);
+                    self.select_row(todos, row);
+                }
+            },
+        }
+    }
+
+    pub fn get_new_todo_parent(&mut self) -> Option<Uuid> {
+        self.new_todo_parent
+    }
+
+    pub fn set_new_todo_parent(&mut self, parent: Option<Uuid>) {
+        self.new_todo_parent = parent;
+    }
+
+    pub fn get_pending_completion(&mut self) -> Option<Uuid> {
+        self.pending_completion
+    }
+
+    pub fn set_pending_completion(&mut self, id: Option<Uuid>) {
+        self.pending_completion = id;
+    }
+
+    pub fn get_pending_deletion(&mut self) -> Option<Uuid> {
+        self.pending_deletion
+    }
+
+    pub fn set_pending_deletion(&mut self, id: Option<Uuid>) {
+        self.pending_deletion = id;
+    }
+
+    pub fn get_pending_purge(&mut self) -> Option<Uuid> {
+        self.pending_purge
+    }
+
+    pub fn set_pending_purge(&mut self, id: Option<Uuid>) {
+        self.pending_purge = id;
+    }
+
+    pub fn is_pending_archive(&mut self) -> bool {
+        self.pending_archive
+    }
+
+    pub fn set_pending_archive(&mut self, pending: bool) {
+        self.pending_archive = pending;
+    }
+
+    pub fn get_archive_days(&mut self) -> u64 {
+        self.archive_days
+    }
+
+    pub fn set_archive_days(&mut self, days: u64) {
+        self.archive_days = days;
+    }
+
+    pub fn get_archive_search(&mut self) -> &String {
+        &self.archive_search
+    }
+
+    pub fn is_searching_archive(&mut self) -> bool {
+        self.is_searching_archive
+    }
+
+    pub fn set_searching_archive(&mut self, is_searching: bool) {
+        self.is_searching_archive = is_searching;
+    }
+
+    pub fn push_archive_search(&mut self, c: char) {
+        if self.archive_search.len() + c.len_utf8() <= MAX_SEARCH_LEN {
+            self.archive_search.push(c);
+            self.archive_list.select_first();
+        }
+    }
+
+    pub fn pop_archive_search(&mut self) {
+        self.archive_search.pop();
+        self.archive_list.select_first();
+    }
+
+    pub fn clear_archive_search(&mut self) {
+        self.archive_search.clear();
+        self.archive_list.select_first();
+    }
+
+    pub fn get_linking(&mut self) -> Option<Uuid> {
+        self.linking
+    }
+
+    pub fn set_linking(&mut self, id: Option<Uuid>) {
+        self.linking = id;
+    }
+
+    pub fn get_moving(&mut self) -> Option<Uuid> {
+        self.moving
+    }
+
+    pub fn set_moving(&mut self, id: Option<Uuid>) {
+        self.moving = id;
+    }
+
+    pub fn get_new_list_name(&mut self) -> Option<&String> {
+        self.new_list_name.as_ref()
+    }
+
+    pub fn set_new_list_name(&mut self, name: Option<String>) {
+        self.new_list_name = name;
+    }
+
+    pub fn push_new_list_name(&mut self, c: char) {
+        if let Some(name) = &mut self.new_list_name {
+            if name.len() + c.len_utf8() <= MAX_LIST_NAME_LEN {
+                name.push(c);
+            }
+        }
+    }
+
+    pub fn pop_new_list_name(&mut self) {
+        if let Some(name) = &mut self.new_list_name {
+            name.pop();
+        }
+    }
+
+    pub fn is_searching(&mut self) -> bool {
+        self.is_searching
+    }
+
+    pub fn set_searching(&mut self, is_searching: bool) {
+        self.is_searching = is_searching;
+    }
+
+    pub fn start_search(&mut self) {
+        self.is_searching = true;
+        self.selected_before_search = self.selected_todo;
+    }
+
+    /// Stops typing the search and selects the todo that was selected before it started
+    pub fn cancel_search(&mut self, todos: &mut Todos) {
+        self.is_searching = false;
+        if let Some(id) = self.selected_before_search.take() {
+            self.select_id(todos, id);
+        }
+    }
+
+    pub fn get_finder_query(&mut self) -> Option<&String> {
+        self.finder_query.as_ref()
+    }
+
+    pub fn open_finder(&mut self, todos: &mut Todos) {
+        self.finder_query = Some(String::new());
+        self.refresh_finder_hits(todos);
+    }
+
+    pub fn close_finder(&mut self) {
+        self.finder_query = None;
+        self.finder_hits.clear();
+    }
+
+    /// Ranks the todos for the query, the best one being selected again
+    fn refresh_finder_hits(&mut self, todos: &mut Todos) {
+        self.finder_hits = match &self.finder_query {
+            Some(query) => todos.fuzzy_find(query),
+            None => Vec::new(),
+        };
+        self.finder_selected = 0;
+    }
+
+    pub fn push_finder_query(&mut self, todos: &mut Todos, c: char) {
+        if let Some(query) = &mut self.finder_query {
+            if query.len() + c.len_utf8() <= MAX_SEARCH_LEN {
+                query.push(c);
+                self.refresh_finder_hits(todos);
+            }
+        }
+    }
+
+    pub fn pop_finder_query(&mut self, todos: &mut Todos) {
+        if let Some(query) = &mut self.finder_query {
+            query.pop();
+            self.refresh_finder_hits(todos);
+        }
+    }
+
+    pub fn get_finder_hits(&mut self) -> &[FuzzyHit] {
+        &self.finder_hits
+    }
+
+    pub fn get_finder_selected(&mut self) -> usize {
+        self.finder_selected
+    }
+
+    pub fn scroll_finder_down(&mut self) {
+        if self.finder_selected + 1 < self.finder_hits.len() {
+            self.finder_selected += 1;
+        }
+    }
+
+    pub fn scroll_finder_up(&mut self) {
+        self.finder_selected = self.finder_selected.saturating_sub(1);
+    }
+
     pub fn get_screen(&mut self) -> &Screens {
         &self.screen
     }
@@ -64,16 +399,178 @@ impl States {
         self.is_in_writting_mode
     }
 
-    pub fn get_title(&mut self) -> &String {
-        &self.title_string
+    pub fn get_title(&mut self) -> &str {
+        self.title_input.as_str()
     }
 
-    pub fn get_date(&mut self) -> &String {
-        &self.date_string
+    pub fn get_description(&mut self) -> &str {
+        self.description_input.as_str()
     }
 
-    pub fn get_description(&mut self) -> &String {
-        &self.description_string
+    pub fn get_input(&mut self, tab: CreateTab) -> Option<&TextInput> {
+        match tab {
+            CreateTab::Title => Some(&self.title_input),
+            CreateTab::Date => Some(&self.date_input),
+            CreateTab::Description => Some(&self.description_input),
+            CreateTab::Tags => Some(&self.tags_input),
+            CreateTab::Recurrence => Some(&self.recurrence_input),
+            CreateTab::Priority => None,
+        }
+    }
+
+    /// The input of the selected tab, the priority being picked rather than written
+    pub fn get_selected_input(&mut self) -> Option<&mut TextInput> {
+        match self.selected_tab {
+            CreateTab::Title => Some(&mut self.title_input),
+            CreateTab::Date => Some(&mut self.date_input),
+            CreateTab::Description => Some(&mut self.description_input),
+            CreateTab::Tags => Some(&mut self.tags_input),
+            CreateTab::Recurrence => Some(&mut self.recurrence_input),
+            CreateTab::Priority => None,
+        }
+    }
+
+    pub fn parse_recurrence(&mut self) -> Result<Option<Recurrence>, String> {
+        Recurrence::parse_input(self.recurrence_input.as_str())
+    }
+
+    pub fn get_tags(&mut self) -> Vec<String> {
+        parse_tags(self.tags_input.as_str())
+    }
+
+    /// Existing tags starting like the one being written, that aren't already given
+    pub fn get_tag_suggestions(&mut self, all_tags: &[String]) -> Vec<String> {
+        let tags = self.tags_input.as_str();
+        let partial = match tags.ends_with([',', ' ']) {
+            true => "",
+            false => tags
+                .rsplit([',', ' '])
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches('#'),
+        };
+        if partial.is_empty() {
+            return Vec::new();
+        }
+        let partial = partial.to_lowercase();
+        let given = self.get_tags();
+        Vec::from_iter(
+            all_tags
+                .iter()
+                .filter(|tag| tag.to_lowercase().starts_with(&partial))
+                .filter(|tag| !given.contains(tag))
+                .cloned(),
+        )
+    }
+
+    /// Replaces the tag being written with the first suggestion, the cursor going to the end
+    pub fn complete_tag(&mut self, all_tags: &[String]) {
+        if let Some(suggestion) = self.get_tag_suggestions(all_tags).first() {
+            let mut tags = String::from(self.tags_input.as_str());
+            let start = tags.rfind([',', ' ']).map_or(0, |idx| idx + 1);
+            tags.truncate(start);
+            if start > 0 && !tags.ends_with(' ') {
+                tags.push(' ');
+            }
+            let completed = format!("{suggestion}, ");
+            if tags.len() + completed.len() <= MAX_TAGS_LEN {
+                tags.push_str(&completed);
+                self.tags_input.set(&tags);
+            }
+        }
+    }
+
+    pub fn get_priority(&mut self) -> Priority {
+        self.priority
+    }
+
+    pub fn raise_priority(&mut self) {
+        self.priority = self.priority.next();
+    }
+
+    pub fn lower_priority(&mut self) {
+        self.priority = self.priority.previous();
+    }
+
+    /// Parses the due date being written, an empty one meaning the todo has no due date
+    pub fn parse_date(&mut self) -> Result<Option<DueDate>, String> {
+        let date = self.date_input.as_str();
+        match date.trim().is_empty() {
+            true => Ok(None),
+            false => DueDate::parse_input(date, Local::now().date_naive()).map(Some),
+        }
+    }
+
+    pub fn get_calendar(&mut self) -> Option<NaiveDate> {
+        self.calendar
+    }
+
+    /// Opens the calendar on the date being written if it is valid, on today otherwise
+    pub fn open_calendar(&mut self) {
+        let today = Local::now().date_naive();
+        self.calendar = Some(match self.parse_date() {
+            Ok(Some(due_date)) => due_date.date,
+            _ => today,
+        });
+    }
+
+    pub fn close_calendar(&mut self) {
+        self.calendar = None;
+    }
+
+    pub fn move_calendar_days(&mut self, days: i64) {
+        self.calendar = self.calendar.and_then(|date| match days.is_negative() {
+            true => date.checked_sub_days(Days::new(days.unsigned_abs())),
+            false => date.checked_add_days(Days::new(days.unsigned_abs())),
+        });
+    }
+
+    pub fn move_calendar_months(&mut self, months: i32) {
+        self.calendar = self.calendar.and_then(|date| match months.is_negative() {
+            true => date.checked_sub_months(Months::new(months.unsigned_abs())),
+            false => date.checked_add_months(Months::new(months.unsigned_abs())),
+        });
+    }
+
+    pub fn move_calendar_to_today(&mut self) {
+        if self.calendar.is_some() {
+            self.calendar = Some(Local::now().date_naive());
+        }
+    }
+
+    /// Writes the day selected in the calendar as the due date, keeping the time already written
+    pub fn pick_calendar_date(&mut self) {
+        if let Some(date) = self.calendar.take() {
+            let due_date = match self.parse_date() {
+                Ok(Some(due_date)) => DueDate { date, ..due_date },
+                _ => DueDate {
+                    date,
+                    time: None,
+                    offset: None,
+                },
+            };
+            self.date_input.set(&due_date.to_string());
+        }
+    }
+
+    pub fn get_error_message(&mut self) -> Option<&String> {
+        self.error_message.as_ref()
+    }
+
+    pub fn set_error_message(&mut self, message: String) {
+        self.error_message = Some(message);
+    }
+
+    pub fn clear_error_message(&mut self) {
+        self.error_message = None;
+    }
+
+    pub fn get_notice(&mut self) -> Option<&String> {
+        self.notice.as_ref()
+    }
+
+    pub fn set_notice(&mut self, notice: Option<String>) {
+        self.notice = notice;
     }
 
     // Setters
@@ -89,84 +586,68 @@ impl States {
         self.file_path = file_path
     }
 
-    pub fn add_char(&mut self, c: char) {
-        match self.selected_tab {
-            CreateTab::Title => {
-                if self.title_string.len() < MAX_TITLE_LEN {
-                    self.title_string.push(c);
-                }
-            }
-            CreateTab::Date => {
-                if self.date_string.len() < MAX_DATE_LEN {
-                    self.date_string.push(c);
-                }
-            }
-            CreateTab::Description => {
-                if self.description_string.len() < MAX_DESCRIPTION_LEN {
-                    self.description_string.push(c);
-                }
-            }
-        }
+    pub fn request_editor(&mut self) {
+        self.editor_requested = true;
     }
 
-    pub fn add_str(&mut self, s: &str) {
-        match self.selected_tab {
-            CreateTab::Title => {
-                if self.title_string.len() + s.len() < MAX_TITLE_LEN {
-                    self.title_string.push_str(s);
-                }
-            }
-            CreateTab::Date => {
-                if self.date_string.len() + s.len() < MAX_DATE_LEN {
-                    self.date_string.push_str(s);
-                }
-            }
-            CreateTab::Description => {
-                if self.description_string.len() + s.len() < MAX_DESCRIPTION_LEN {
-                    self.description_string.push_str(s);
-                }
-            }
-        }
+    pub fn take_editor_request(&mut self) -> bool {
+        std::mem::take(&mut self.editor_requested)
     }
 
+    /// Fills the inputs with the fields of the selected todo, emptying those it doesn't have
     pub fn init_edit_mode(&mut self, todos: &mut Todos) {
-        if let Some(idx) = self.todo_list.selected() {
-            if let Some(todo) = &todos.get_todo(idx) {
-                self.title_string.push_str(&todo.title);
-                self.date_string.push_str(&todo.due_date);
-                self.description_string.push_str(&todo.description);
+        if let Some(id) = self.selected_todo {
+            if let Some(todo) = &todos.get_todo(id) {
+                self.clear_strings();
+                self.priority = todo.priority;
+                self.tags_input.set(&todo.tags.join(", "));
+                if let Some(recurrence) = &todo.recurrence {
+                    self.recurrence_input.set(&recurrence.to_string());
+                }
+                self.title_input.set(&todo.title);
+                if let Some(due_date) = todo.due_date {
+                    self.date_input.set(&due_date.to_string());
+                }
+                self.description_input.set(&todo.description);
             }
         }
     }
 
-    pub fn pop_char(&mut self) {
-        match self.selected_tab {
-            CreateTab::Title => self.title_string.pop(),
-            CreateTab::Date => self.date_string.pop(),
-            CreateTab::Description => self.description_string.pop(),
-        };
-    }
-
     pub fn get_nb_char_in_tab(&mut self, tab: CreateTab) -> usize {
         match tab {
-            CreateTab::Title => self.title_string.len(),
-            CreateTab::Date => self.date_string.len(),
-            CreateTab::Description => self.description_string.len(),
+            CreateTab::Title => self.title_input.len(),
+            CreateTab::Date => self.date_input.len(),
+            CreateTab::Description => self.description_input.len(),
+            CreateTab::Tags => self.tags_input.len(),
+            CreateTab::Recurrence => self.recurrence_input.len(),
+            CreateTab::Priority => 0,
         }
     }
 
     pub fn next_tab(&mut self) {
         self.selected_tab = match self.selected_tab {
             CreateTab::Title => CreateTab::Date,
-            CreateTab::Date => CreateTab::Description,
+            CreateTab::Date => CreateTab::Priority,
+            CreateTab::Priority => CreateTab::Tags,
+            CreateTab::Tags => CreateTab::Recurrence,
+            CreateTab::Recurrence => CreateTab::Description,
             CreateTab::Description => CreateTab::Title,
         }
     }
 
     pub fn clear_strings(&mut self) {
-        self.title_string.clear();
-        self.date_string.clear();
-        self.description_string.clear();
+        self.title_input.clear();
+        self.date_input.clear();
+        self.description_input.clear();
+        self.tags_input.clear();
+        self.recurrence_input.clear();
+        self.priority = Priority::None;
+        self.error_message = None;
+        self.new_todo_parent = None;
+    }
+
+    pub fn is_selected_tab(&mut self, tab: CreateTab) -> bool {
+        tab == self.selected_tab
     }
 
     pub fn get_fg_color_for_tab(&mut self, tab: CreateTab) -> Color {
@@ -178,20 +659,81 @@ impl States {
 
     pub fn scroll_down(&mut self, todos: &mut Todos) {
         if let Some(idx) = self.todo_list.selected() {
-            if idx == todos.get_nb_todos() - 1 {
-                self.todo_list.select_first();
+            if idx + 1 >= todos.get_nb_rows() {
+                self.select_first(todos);
+            } else {
+                self.select_row(todos, idx + 1);
+            }
+        }
+    }
+
+    pub fn scroll_backups_down(&mut self, nb_backups: usize) {
+        if let Some(idx) = self.backup_list.selected() {
+            if idx + 1 >= nb_backups {
+                self.backup_list.select_first();
+            } else {
+                self.backup_list.select_next();
+            }
+        }
+    }
+
+    pub fn scroll_backups_up(&mut self) {
+        if let Some(idx) = self.backup_list.selected() {
+            if idx == 0 {
+                self.backup_list.select_last();
+            } else {
+                self.backup_list.select_previous();
+            }
+        }
+    }
+
+    pub fn scroll_trash_down(&mut self, nb_entries: usize) {
+        if let Some(idx) = self.trash_list.selected() {
+            if idx + 1 >= nb_entries {
+                self.trash_list.select_first();
+            } else {
+                self.trash_list.select_next();
+            }
+        }
+    }
+
+    pub fn scroll_archive_down(&mut self, nb_todos: usize) {
+        if let Some(idx) = self.archive_list.selected() {
+            if idx + 1 >= nb_todos {
+                self.archive_list.select_first();
+            } else {
+                self.archive_list.select_next();
+            }
+        }
+    }
+
+    pub fn scroll_archive_up(&mut self) {
+        if let Some(idx) = self.archive_list.selected() {
+            if idx == 0 {
+                self.archive_list.select_last();
+            } else {
+                self.archive_list.select_previous();
+            }
+        }
+    }
+
+    pub fn scroll_trash_up(&mut self) {
+        if let Some(idx) = self.trash_list.selected() {
+            if idx == 0 {
+                self.trash_list.select_last();
             } else {
-                self.todo_list.select_next();
+                self.trash_list.select_previous();
             }
         }
     }
 
-    pub fn scroll_up(&mut self) {
+    pub fn scroll_up(&mut self, todos: &mut Todos) {
         if let Some(idx) = self.todo_list.selected() {
             if idx == 0 {
-                self.todo_list.select_last();
+                let last = todos.get_nb_rows().saturating_sub(1);
+                self.select_row(todos, last);
             } else {
-                self.todo_list.select_previous();
+                self.select_row(todos, idx - 1);
             }
         }
     }
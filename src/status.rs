@@ -0,0 +1,152 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::{fs, io};
+
+/// What being in a status means for a todo, whatever the status is called
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusKind {
+    /// Still to be done, whether it was started or not
+    Open,
+    Done,
+    /// Won't be done, which doesn't hold up anything either
+    Cancelled,
+}
+
+impl StatusKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusKind::Open => "open",
+            StatusKind::Done => "done",
+            StatusKind::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Status {
+    pub name: String,
+    /// Symbol shown before the title of the todos in this status
+    pub glyph: String,
+    pub kind: StatusKind,
+}
+
+/// The statuses todos go through, in the order they are cycled
+#[derive(Clone)]
+pub struct Statuses(Vec<Status>);
+
+fn status(name: &str, glyph: &str, kind: StatusKind) -> Status {
+    Status {
+        name: String::from(name),
+        glyph: String::from(glyph),
+        kind,
+    }
+}
+
+impl Default for Statuses {
+    fn default() -> Self {
+        Statuses(vec![
+            status("todo", "✘", StatusKind::Open),
+            status("doing", "▶", StatusKind::Open),
+            status("done", "✔", StatusKind::Done),
+            status("cancelled", "⊘", StatusKind::Cancelled),
+        ])
+    }
+}
+
+impl Statuses {
+    /// Reads the statuses from a JSON list such as
+    /// `[{ "name": "waiting", "glyph": "⏸", "kind": "open" }]`, the default ones being used
+    /// when the file doesn't exist
+    pub fn load(file_path: &str) -> Result<Statuses, String> {
+        match fs::read_to_string(file_path) {
+            Ok(contents) => Statuses::parse(&contents),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Statuses::default()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    /// Reads the statuses from the contents of a statuses file
+    pub fn parse(contents: &str) -> Result<Statuses, String> {
+        let statuses: Vec<Status> =
+            serde_json::from_str(contents).map_err(|error| error.to_string())?;
+
+        for (idx, status) in statuses.iter().enumerate() {
+            if statuses[..idx].iter().any(|s| s.name == status.name) {
+                return Err(format!("the status '{}' is listed twice", status.name));
+            }
+        }
+        for kind in [StatusKind::Open, StatusKind::Done] {
+            if !statuses.iter().any(|status| status.kind == kind) {
+                return Err(format!(
+                    "there has to be a status of kind '{}'",
+                    kind.as_str()
+                ));
+            }
+        }
+        Ok(Statuses(statuses))
+    }
+
+    fn get(&self, name: &str) -> Option<&Status> {
+        self.0.iter().find(|status| status.name == name)
+    }
+
+    /// Statuses that aren't known anymore, because they were removed from the file, count as
+    /// open so that their todos aren't lost from sight
+    pub fn kind(&self, name: &str) -> StatusKind {
+        self.get(name)
+            .map_or(StatusKind::Open, |status| status.kind)
+    }
+
+    /// Whether the todo is done or cancelled, nothing being left to do on it
+    pub fn is_closed(&self, name: &str) -> bool {
+        self.kind(name) != StatusKind::Open
+    }
+
+    pub fn glyph(&self, name: &str) -> &str {
+        self.get(name).map_or("?", |status| status.glyph.as_str())
+    }
+
+    /// Open todos keep the color of the text
+    pub fn color(&self, name: &str) -> Option<Color> {
+        match self.kind(name) {
+            StatusKind::Open => None,
+            StatusKind::Done => Some(Color::Green),
+            StatusKind::Cancelled => Some(Color::DarkGray),
+        }
+    }
+
+    fn first_of(&self, kind: StatusKind) -> &str {
+        self.0
+            .iter()
+            .find(|status| status.kind == kind)
+            .map_or("", |status| status.name.as_str())
+    }
+
+    /// Status of the todos that are added, or reopened
+    pub fn initial(&self) -> &str {
+        self.first_of(StatusKind::Open)
+    }
+
+    /// Status of the todos that are completed along with their parent
+    pub fn done(&self) -> &str {
+        self.first_of(StatusKind::Done)
+    }
+
+    /// Rank of the status in the cycle, unknown statuses coming after the others
+    pub fn position(&self, name: &str) -> usize {
+        self.0
+            .iter()
+            .position(|status| status.name == name)
+            .unwrap_or(self.0.len())
+    }
+
+    /// The status coming after the given one, going back to the first one after the last
+    pub fn next(&self, name: &str) -> &str {
+        let next = match self.0.iter().position(|status| status.name == name) {
+            Some(idx) => (idx + 1) % self.0.len(),
+            None => 0,
+        };
+        self.0[next].name.as_str()
+    }
+}
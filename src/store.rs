@@ -0,0 +1,139 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::todo::Todo;
+
+pub mod format;
+pub mod json;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// A single mutation of the todos list, given to the store so that backends
+/// able to persist one entry at a time don't have to rewrite everything
+pub enum StoreChange {
+    Added(usize),
+    Edited(usize),
+    Deleted(usize),
+}
+
+/// Where the stored data stopped making sense, kept to show it to the user
+pub struct CorruptData {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    /// Raw contents of the line containing the error
+    pub excerpt: String,
+}
+
+pub enum LoadError {
+    Io(io::Error),
+    Corrupt(CorruptData),
+    /// The todos could be read but must not be written back
+    ReadOnly {
+        todos: Vec<Todo>,
+        reason: String,
+    },
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+pub trait TodoStore {
+    fn load(&mut self) -> Result<Vec<Todo>, LoadError>;
+
+    fn save(&mut self, todos: &[Todo]) -> io::Result<()>;
+
+    /// Persists a single change, `todos` being the list once the change has been applied.
+    ///
+    /// Stores that can't do better simply save the whole list
+    fn apply(&mut self, change: StoreChange, todos: &[Todo]) -> io::Result<()> {
+        let _ = change;
+        self.save(todos)
+    }
+
+    /// Persists the changes made one after the other, whole or not at all, `todos` being the list
+    /// once they have all been made.
+    ///
+    /// Stores that can't do better simply save the whole list
+    fn apply_all(&mut self, changes: &[StoreChange], todos: &[Todo]) -> io::Result<()> {
+        let _ = changes;
+        self.save(todos)
+    }
+
+    /// Describes where the todos are stored, used in error messages
+    fn location(&self) -> String;
+
+    /// Whether the store keeps backups that can be restored
+    fn keeps_backups(&self) -> bool {
+        false
+    }
+
+    /// Takes the error of the last backup, which doesn't keep the todos from being saved
+    fn take_backup_error(&mut self) -> Option<io::Error> {
+        None
+    }
+
+    /// Lists the backups that can be restored, the most recent first
+    fn backups(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Replaces the stored todos with the ones from the backup, and returns them
+    fn restore(&mut self, backup: &Path) -> io::Result<Vec<Todo>> {
+        let _ = backup;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "this store doesn't keep backups",
+        ))
+    }
+
+    /// Extracts the todos that can still be read from corrupted data
+    fn salvage(&mut self) -> Vec<Todo> {
+        Vec::new()
+    }
+}
+
+/// Store keeping the todos in memory, shared with the test that created it so that it can look
+/// at what was written
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    pub todos: std::rc::Rc<std::cell::RefCell<Vec<Todo>>>,
+    /// Number of writes made to the store
+    pub writes: std::rc::Rc<std::cell::Cell<usize>>,
+    /// Makes every write fail while set
+    pub fail: std::rc::Rc<std::cell::Cell<bool>>,
+}
+
+#[cfg(test)]
+impl MemoryStore {
+    /// Titles of the stored top-level todos, in their order
+    pub fn titles(&self) -> Vec<String> {
+        Vec::from_iter(self.todos.borrow().iter().map(|todo| todo.title.clone()))
+    }
+}
+
+#[cfg(test)]
+impl TodoStore for MemoryStore {
+    fn load(&mut self) -> Result<Vec<Todo>, LoadError> {
+        Ok(self.todos.borrow().clone())
+    }
+
+    fn save(&mut self, todos: &[Todo]) -> io::Result<()> {
+        if self.fail.get() {
+            return Err(io::Error::other("the store is failing"));
+        }
+        self.writes.set(self.writes.get() + 1);
+        *self.todos.borrow_mut() = todos.to_vec();
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        String::from("memory")
+    }
+}
@@ -0,0 +1,472 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{due_date::migrate_due_date_string, status::Statuses, todo::Todo};
+
+/// Version of the format written by this build, to bump every time the todos change in a way
+/// older builds couldn't read back without losing data
+pub const FORMAT_VERSION: u64 = 11;
+
+/// Migrates a file to the next version, given the statuses of the todos for the versions that
+/// predate them
+type Migration = fn(Value, &Statuses) -> Value;
+
+/// Migrations from each past version to the next one, `MIGRATIONS[n]` taking a file of
+/// version `n + 1`
+const MIGRATIONS: &[Migration] = &[
+    v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9, v9_to_v10,
+    v10_to_v11,
+];
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    format_version: u64,
+    todos: &'a [Todo],
+}
+
+#[derive(Deserialize)]
+struct OwnedEnvelope {
+    todos: Vec<Todo>,
+}
+
+pub enum DecodeError {
+    Invalid(serde_json::Error),
+    /// The file was written by a newer build, its todos being read on a best-effort basis
+    Newer {
+        version: u64,
+        todos: Option<Vec<Todo>>,
+    },
+}
+
+/// Version 1 was a bare array of todos
+fn v1_to_v2(todos: Value, _: &Statuses) -> Value {
+    json!({ "format_version": 2, "todos": todos })
+}
+
+/// Version 2 stored the due dates as free-form strings
+fn v2_to_v3(mut file: Value, _: &Statuses) -> Value {
+    if let Some(todos) = file.get_mut("todos").and_then(Value::as_array_mut) {
+        for todo in todos {
+            let due_date = String::from(todo["due_date"].as_str().unwrap_or_default());
+            let mut description = String::from(todo["description"].as_str().unwrap_or_default());
+            todo["due_date"] = match migrate_due_date_string(&due_date, &mut description) {
+                Some(due_date) => json!(due_date.to_string()),
+                None => Value::Null,
+            };
+            todo["description"] = json!(description);
+        }
+    }
+    file["format_version"] = json!(3);
+    file
+}
+
+/// Version 3 had no priorities
+fn v3_to_v4(mut file: Value, _: &Statuses) -> Value {
+    if let Some(todos) = file.get_mut("todos").and_then(Value::as_array_mut) {
+        for todo in todos {
+            todo["priority"] = json!("none");
+        }
+    }
+    file["format_version"] = json!(4);
+    file
+}
+
+/// Version 4 had no tags
+fn v4_to_v5(mut file: Value, _: &Statuses) -> Value {
+    if let Some(todos) = file.get_mut("todos").and_then(Value::as_array_mut) {
+        for todo in todos {
+            todo["tags"] = json!([]);
+        }
+    }
+    file["format_version"] = json!(5);
+    file
+}
+
+/// Version 5 had no subtasks
+fn v5_to_v6(mut file: Value, _: &Statuses) -> Value {
+    if let Some(todos) = file.get_mut("todos").and_then(Value::as_array_mut) {
+        for todo in todos {
+            todo["children"] = json!([]);
+            todo["collapsed"] = json!(false);
+        }
+    }
+    file["format_version"] = json!(6);
+    file
+}
+
+/// Calls `f` on each todo of the list and on their subtasks
+fn for_each_todo(todos: &mut Value, f: &impl Fn(&mut Value)) {
+    if let Some(todos) = todos.as_array_mut() {
+        for todo in todos {
+            f(todo);
+            if let Some(children) = todo.get_mut("children") {
+                for_each_todo(children, f);
+            }
+        }
+    }
+}
+
+/// Gives an id to the todos and their subtasks, which had none before they could be linked
+pub fn add_ids(todos: &mut Value) {
+    for_each_todo(todos, &|todo| {
+        todo["id"] = json!(Uuid::new_v4());
+        todo["blocked_by"] = json!([]);
+    });
+}
+
+/// Version 6 had no ids nor links between todos
+fn v6_to_v7(mut file: Value, _: &Statuses) -> Value {
+    add_ids(&mut file["todos"]);
+    file["format_version"] = json!(7);
+    file
+}
+
+/// Version 7 had no repeating todos
+fn v7_to_v8(mut file: Value, _: &Statuses) -> Value {
+    for_each_todo(&mut file["todos"], &|todo| {
+        todo["recurrence"] = Value::Null;
+        todo["completions"] = json!([]);
+    });
+    file["format_version"] = json!(8);
+    file
+}
+
+/// Version 8 didn't record when the todos were created, updated or completed, which stays
+/// unknown for them
+fn v8_to_v9(mut file: Value, _: &Statuses) -> Value {
+    for_each_todo(&mut file["todos"], &|todo| {
+        todo["created_at"] = Value::Null;
+        todo["updated_at"] = Value::Null;
+        todo["completed_at"] = Value::Null;
+    });
+    file["format_version"] = json!(9);
+    file
+}
+
+/// Replaces whether the todos and their subtasks were done with the first status of the matching
+/// kind
+pub fn statuses_from_done(todos: &mut Value, statuses: &Statuses) {
+    for_each_todo(todos, &|todo| {
+        let done = todo
+            .as_object_mut()
+            .and_then(|todo| todo.remove("done"))
+            .and_then(|done| done.as_bool())
+            .unwrap_or_default();
+        todo["status"] = json!(match done {
+            true => statuses.done(),
+            false => statuses.initial(),
+        });
+    });
+}
+
+/// Version 9 only knew whether the todos were done
+fn v9_to_v10(mut file: Value, statuses: &Statuses) -> Value {
+    statuses_from_done(&mut file["todos"], statuses);
+    file["format_version"] = json!(10);
+    file
+}
+
+/// Version 10 had a single list of todos
+fn v10_to_v11(mut file: Value, _: &Statuses) -> Value {
+    if let Some(todos) = file.get_mut("todos").and_then(Value::as_array_mut) {
+        for todo in todos {
+            todo["list"] = json!("inbox");
+        }
+    }
+    file["format_version"] = json!(11);
+    file
+}
+
+fn get_version(value: &Value) -> Option<u64> {
+    match value {
+        Value::Array(_) => Some(1),
+        Value::Object(map) => map.get("format_version").and_then(Value::as_u64),
+        _ => None,
+    }
+}
+
+pub fn encode(todos: &[Todo]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&Envelope {
+        format_version: FORMAT_VERSION,
+        todos,
+    })
+}
+
+/// Reads todos from any version of the format, migrating them to the current one
+pub fn decode(contents: &str, statuses: &Statuses) -> Result<Vec<Todo>, DecodeError> {
+    let value: Value = serde_json::from_str(contents).map_err(DecodeError::Invalid)?;
+    let version = match get_version(&value) {
+        Some(version) => version,
+        None => {
+            return Err(DecodeError::Invalid(serde::de::Error::custom(
+                "expected a list of todos or a 'format_version' field",
+            )))
+        }
+    };
+
+    if version > FORMAT_VERSION {
+        return Err(DecodeError::Newer {
+            version,
+            todos: serde_json::from_value::<OwnedEnvelope>(value)
+                .ok()
+                .map(|envelope| envelope.todos),
+        });
+    }
+    if version == FORMAT_VERSION {
+        // Parsed from the text rather than the value so that errors keep their position
+        return serde_json::from_str::<OwnedEnvelope>(contents)
+            .map(|envelope| envelope.todos)
+            .map_err(DecodeError::Invalid);
+    }
+
+    let migrated = MIGRATIONS
+        .iter()
+        .skip(version.saturating_sub(1) as usize)
+        .fold(value, |value, migration| migration(value, statuses));
+    serde_json::from_value::<OwnedEnvelope>(migrated)
+        .map(|envelope| envelope.todos)
+        .map_err(DecodeError::Invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{due_date::recurrence::Recurrence, priority::Priority};
+
+    fn decode_ok(contents: &str) -> Vec<Todo> {
+        match decode(contents, &Statuses::default()) {
+            Ok(todos) => todos,
+            Err(DecodeError::Invalid(error)) => panic!("invalid fixture: {error}"),
+            Err(DecodeError::Newer { version, .. }) => panic!("fixture of newer version {version}"),
+        }
+    }
+
+    fn due_dates(todos: &[Todo]) -> Vec<Option<String>> {
+        Vec::from_iter(
+            todos
+                .iter()
+                .map(|todo| todo.due_date.map(|d| d.to_string())),
+        )
+    }
+
+    #[test]
+    fn v1_bare_array_is_migrated() {
+        let todos = decode_ok(
+            r#"[
+                {"title": "milk", "description": "", "due_date": "2024-12-31", "done": true},
+                {"title": "bread", "description": "soon", "due_date": "", "done": false}
+            ]"#,
+        );
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].title, "milk");
+        assert_eq!(todos[0].status, "done");
+        assert_eq!(todos[1].status, "todo");
+        assert_eq!(due_dates(&todos), [Some(String::from("2024-12-31")), None]);
+        for todo in &todos {
+            assert_eq!(todo.priority, Priority::None);
+            assert!(todo.tags.is_empty() && todo.children.is_empty() && !todo.collapsed);
+            assert!(todo.blocked_by.is_empty() && todo.recurrence.is_none());
+            assert!(todo.created_at.is_none() && todo.completed_at.is_none());
+            assert_eq!(todo.list, "inbox");
+        }
+        assert_ne!(todos[0].id, todos[1].id);
+    }
+
+    #[test]
+    fn v2_free_form_due_dates_are_parsed_or_kept_in_the_description() {
+        let todos = decode_ok(
+            r#"{"format_version": 2, "todos": [
+                {"title": "call", "description": "", "due_date": "2024-12-31 18:30", "done": false},
+                {"title": "read", "description": "a book", "due_date": "someday", "done": false}
+            ]}"#,
+        );
+        assert_eq!(
+            due_dates(&todos),
+            [Some(String::from("2024-12-31 18:30")), None]
+        );
+        assert_eq!(todos[0].description, "");
+        assert_eq!(todos[1].description, "a book\n\nDue date: someday");
+    }
+
+    #[test]
+    fn v3_todos_get_no_priority() {
+        let todos = decode_ok(
+            r#"{"format_version": 3, "todos": [
+                {"title": "call", "description": "", "due_date": "2024-12-31", "done": false}
+            ]}"#,
+        );
+        assert_eq!(due_dates(&todos), [Some(String::from("2024-12-31"))]);
+        assert_eq!(todos[0].priority, Priority::None);
+    }
+
+    #[test]
+    fn v4_priorities_are_kept_and_tags_added() {
+        let todos = decode_ok(
+            r#"{"format_version": 4, "todos": [
+                {"title": "call", "description": "", "due_date": null, "done": false, "priority": "high"}
+            ]}"#,
+        );
+        assert_eq!(todos[0].priority, Priority::High);
+        assert!(todos[0].tags.is_empty());
+    }
+
+    #[test]
+    fn v5_tags_are_kept_and_subtasks_added() {
+        let todos = decode_ok(
+            r#"{"format_version": 5, "todos": [
+                {"title": "call", "description": "", "due_date": null, "done": false,
+                 "priority": "low", "tags": ["home", "phone"]}
+            ]}"#,
+        );
+        assert_eq!(todos[0].tags, ["home", "phone"]);
+        assert!(todos[0].children.is_empty());
+        assert!(!todos[0].collapsed);
+    }
+
+    #[test]
+    fn v6_todos_and_subtasks_get_distinct_ids() {
+        let todos = decode_ok(
+            r#"{"format_version": 6, "todos": [
+                {"title": "trip", "description": "", "due_date": null, "done": false,
+                 "priority": "none", "tags": [], "collapsed": true, "children": [
+                    {"title": "tickets", "description": "", "due_date": null, "done": true,
+                     "priority": "none", "tags": [], "children": [], "collapsed": false}
+                ]}
+            ]}"#,
+        );
+        let subtask = &todos[0].children[0];
+        assert!(todos[0].collapsed);
+        assert_eq!(subtask.title, "tickets");
+        assert_eq!(subtask.status, "done");
+        assert_ne!(todos[0].id, subtask.id);
+        assert!(todos[0].blocked_by.is_empty() && subtask.blocked_by.is_empty());
+    }
+
+    #[test]
+    fn v7_ids_and_links_are_kept() {
+        let todos = decode_ok(
+            r#"{"format_version": 7, "todos": [
+                {"id": "00000000-0000-0000-0000-000000000001", "title": "pack",
+                 "description": "", "due_date": null, "done": false, "priority": "none",
+                 "tags": [], "children": [], "collapsed": false,
+                 "blocked_by": ["00000000-0000-0000-0000-000000000002"]}
+            ]}"#,
+        );
+        assert_eq!(todos[0].id, Uuid::from_u128(1));
+        assert_eq!(todos[0].blocked_by, [Uuid::from_u128(2)]);
+        assert!(todos[0].recurrence.is_none());
+        assert!(todos[0].completions.is_empty());
+    }
+
+    #[test]
+    fn v8_recurrences_are_kept_and_timestamps_unknown() {
+        let todos = decode_ok(
+            r#"{"format_version": 8, "todos": [
+                {"id": "00000000-0000-0000-0000-000000000001", "title": "water the plants",
+                 "description": "", "due_date": "2024-12-31", "done": false, "priority": "none",
+                 "tags": [], "children": [], "collapsed": false, "blocked_by": [],
+                 "recurrence": "daily", "completions": []}
+            ]}"#,
+        );
+        assert_eq!(todos[0].recurrence, Some(Recurrence::Daily));
+        assert!(todos[0].created_at.is_none());
+        assert!(todos[0].updated_at.is_none());
+        assert!(todos[0].completed_at.is_none());
+    }
+
+    #[test]
+    fn v9_done_flags_become_statuses() {
+        let todos = decode_ok(
+            r#"{"format_version": 9, "todos": [
+                {"id": "00000000-0000-0000-0000-000000000001", "title": "trip",
+                 "description": "", "due_date": null, "done": true, "priority": "none",
+                 "tags": [], "collapsed": false, "blocked_by": [], "recurrence": null,
+                 "completions": [], "created_at": null, "updated_at": null, "completed_at": null,
+                 "children": [
+                    {"id": "00000000-0000-0000-0000-000000000002", "title": "tickets",
+                     "description": "", "due_date": null, "done": false, "priority": "none",
+                     "tags": [], "children": [], "collapsed": false, "blocked_by": []}
+                ]}
+            ]}"#,
+        );
+        assert_eq!(todos[0].status, "done");
+        assert_eq!(todos[0].children[0].status, "todo");
+    }
+
+    #[test]
+    fn v9_done_flags_become_the_configured_statuses() {
+        let statuses = Statuses::parse(
+            r#"[
+                {"name": "later", "glyph": "…", "kind": "open"},
+                {"name": "finished", "glyph": "✔", "kind": "done"}
+            ]"#,
+        )
+        .unwrap();
+        let contents = r#"{"format_version": 9, "todos": [
+            {"title": "call", "description": "", "due_date": null, "done": true},
+            {"title": "read", "description": "", "due_date": null, "done": false}
+        ]}"#;
+        let todos = match decode(contents, &statuses) {
+            Ok(todos) => todos,
+            Err(_) => panic!("invalid fixture"),
+        };
+        assert_eq!(todos[0].status, "finished");
+        assert_eq!(todos[1].status, "later");
+    }
+
+    #[test]
+    fn v10_todos_go_to_the_inbox() {
+        let todos = decode_ok(
+            r#"{"format_version": 10, "todos": [
+                {"id": "00000000-0000-0000-0000-000000000001", "title": "call",
+                 "description": "", "due_date": null, "status": "doing", "priority": "none",
+                 "tags": [], "children": [], "collapsed": false, "blocked_by": [],
+                 "recurrence": null, "completions": [], "created_at": null,
+                 "updated_at": null, "completed_at": null}
+            ]}"#,
+        );
+        assert_eq!(todos[0].status, "doing");
+        assert_eq!(todos[0].list, "inbox");
+    }
+
+    #[test]
+    fn current_version_is_read_as_is() {
+        let todos = decode_ok(
+            r#"{"format_version": 11, "todos": [
+                {"id": "00000000-0000-0000-0000-000000000001", "title": "report",
+                 "description": "", "due_date": null, "status": "todo", "priority": "urgent",
+                 "tags": [], "children": [], "collapsed": false, "blocked_by": [],
+                 "recurrence": null, "completions": [], "created_at": null,
+                 "updated_at": null, "completed_at": null, "list": "work"}
+            ]}"#,
+        );
+        assert_eq!(todos[0].priority, Priority::Urgent);
+        assert_eq!(todos[0].list, "work");
+    }
+
+    #[test]
+    fn encoded_todos_decode_back() {
+        let todos =
+            decode_ok(r#"[{"title": "milk", "description": "", "due_date": "", "done": false}]"#);
+        let decoded = decode_ok(&encode(&todos).unwrap());
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].id, todos[0].id);
+        assert_eq!(decoded[0].title, "milk");
+    }
+
+    #[test]
+    fn newer_versions_are_read_on_a_best_effort_basis() {
+        let contents = format!(
+            r#"{{"format_version": {}, "todos": [], "unknown": true}}"#,
+            FORMAT_VERSION + 1
+        );
+        match decode(&contents, &Statuses::default()) {
+            Err(DecodeError::Newer { version, todos }) => {
+                assert_eq!(version, FORMAT_VERSION + 1);
+                assert!(todos.is_some_and(|todos| todos.is_empty()));
+            }
+            _ => panic!("expected the file to be read as newer"),
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use chrono::{DateTime, Local, TimeDelta};
+use std::{
+    fs::{self, exists, read_to_string, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    status::Statuses,
+    store::{
+        format::{self, DecodeError, FORMAT_VERSION},
+        CorruptData, LoadError, TodoStore,
+    },
+    todo::Todo,
+};
+
+/// Number of backups kept next to the todos file, the oldest ones being removed first
+const MAX_BACKUPS: usize = 10;
+/// Minimum time between two backups, so that a burst of edits doesn't rotate every backup out
+const BACKUP_INTERVAL: TimeDelta = TimeDelta::minutes(10);
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+pub struct JsonStore {
+    file_path: String,
+    last_backup: Option<DateTime<Local>>,
+    /// Set when the file couldn't be parsed, so that it is preserved before being overwritten
+    is_corrupt: bool,
+    /// Set when the file must not be overwritten, as it was written by a newer build
+    read_only_reason: Option<String>,
+    /// Why the last backup failed, until it is reported
+    backup_error: Option<io::Error>,
+    /// Statuses given to the todos of the files that predate them
+    statuses: Statuses,
+}
+
+/// Finds the byte index of the `}` closing the object opened at `start`
+fn find_object_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, &b) in bytes.iter().enumerate().skip(start) {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses every complete object of the file that is a valid todo, whatever the state of the
+/// rest of the file
+fn salvage_todos(file_contents: &str) -> Vec<Todo> {
+    let bytes = file_contents.as_bytes();
+    let mut todos = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'{' {
+            if let Some(end) = find_object_end(bytes, idx) {
+                if let Ok(todo) = serde_json::from_str::<Todo>(&file_contents[idx..=end]) {
+                    todos.push(todo);
+                    idx = end;
+                }
+            }
+        }
+        idx += 1;
+    }
+    todos
+}
+
+impl JsonStore {
+    pub fn new(file_path: String, statuses: Statuses) -> Self {
+        Self {
+            file_path,
+            last_backup: None,
+            is_corrupt: false,
+            read_only_reason: None,
+            backup_error: None,
+            statuses,
+        }
+    }
+
+    fn parse(&self, file_contents: &str) -> Result<Vec<Todo>, LoadError> {
+        if file_contents.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        format::decode(file_contents, &self.statuses).map_err(|error| match error {
+            DecodeError::Invalid(error) => LoadError::Corrupt(CorruptData {
+                message: error.to_string(),
+                line: error.line(),
+                column: error.column(),
+                excerpt: match error.line() {
+                    0 => String::new(),
+                    line => String::from(file_contents.lines().nth(line - 1).unwrap_or_default()),
+                },
+            }),
+            DecodeError::Newer {
+                version,
+                todos: Some(todos),
+            } => LoadError::ReadOnly {
+                todos,
+                reason: Self::newer_version_message(version),
+            },
+            DecodeError::Newer {
+                version,
+                todos: None,
+            } => LoadError::Corrupt(CorruptData {
+                message: Self::newer_version_message(version),
+                line: 0,
+                column: 0,
+                excerpt: String::new(),
+            }),
+        })
+    }
+
+    fn newer_version_message(version: u64) -> String {
+        format!(
+            "written by a newer version of {} (format version {version}, this one supports up to {FORMAT_VERSION})",
+            env!("CARGO_PKG_NAME")
+        )
+    }
+
+    /// Keeps a copy of the corrupt file that will never be rotated out like the backups
+    fn preserve_corrupt_file(&mut self) -> io::Result<()> {
+        let corrupt_path = format!(
+            "{}.corrupt-{}",
+            self.file_path,
+            Local::now().format(BACKUP_TIMESTAMP_FORMAT)
+        );
+        fs::copy(&self.file_path, corrupt_path)?;
+        self.is_corrupt = false;
+        Ok(())
+    }
+
+    /// Prefix shared by the file names of every backup of the todos file
+    fn backup_prefix(&self) -> String {
+        let file_name = Path::new(&self.file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        format!("{file_name}.")
+    }
+
+    /// Copies the current todos file to a timestamped backup and removes the oldest backups
+    fn backup(&mut self) -> io::Result<()> {
+        let now = Local::now();
+        if self
+            .last_backup
+            .is_some_and(|last_backup| now - last_backup < BACKUP_INTERVAL)
+            || fs::metadata(&self.file_path).map_or(true, |metadata| metadata.len() == 0)
+        {
+            return Ok(());
+        }
+
+        let backup_path = format!(
+            "{}.{}.bak",
+            self.file_path,
+            now.format(BACKUP_TIMESTAMP_FORMAT)
+        );
+        if !exists(&backup_path).unwrap_or(false) {
+            fs::copy(&self.file_path, &backup_path)?;
+        }
+        self.last_backup = Some(now);
+
+        for old_backup in self.backups().iter().skip(MAX_BACKUPS) {
+            fs::remove_file(old_backup)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the contents to a temporary file that replaces the file once synced to the disk, so
+/// that a crash mid-write can't leave a truncated file behind
+pub fn write_atomically(file_path: &str, contents: &[u8]) -> io::Result<()> {
+    let tmp_path = format!("{file_path}.tmp");
+    let mut tmp_file = File::create(&tmp_path)?;
+    tmp_file.write_all(contents)?;
+    tmp_file.sync_all()?;
+    fs::rename(&tmp_path, file_path)?;
+
+    // Makes the rename itself durable
+    if let Some(parent) = Path::new(file_path).parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+impl TodoStore for JsonStore {
+    fn load(&mut self) -> Result<Vec<Todo>, LoadError> {
+        if !exists(&self.file_path).unwrap_or(false) {
+            File::create(&self.file_path)?;
+        }
+        let todos = self.parse(&read_to_string(&self.file_path)?);
+        self.is_corrupt = matches!(todos, Err(LoadError::Corrupt(_)));
+        self.read_only_reason = match &todos {
+            Err(LoadError::ReadOnly { reason, .. }) => Some(reason.clone()),
+            _ => None,
+        };
+        todos
+    }
+
+    fn save(&mut self, todos: &[Todo]) -> io::Result<()> {
+        if let Some(reason) = &self.read_only_reason {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                reason.clone(),
+            ));
+        }
+        let json_str = format::encode(todos)?;
+        if self.is_corrupt {
+            self.preserve_corrupt_file()?;
+        }
+        if let Err(error) = self.backup() {
+            self.backup_error = Some(error);
+        }
+        write_atomically(&self.file_path, json_str.as_bytes())
+    }
+
+    fn location(&self) -> String {
+        self.file_path.clone()
+    }
+
+    fn keeps_backups(&self) -> bool {
+        true
+    }
+
+    fn take_backup_error(&mut self) -> Option<io::Error> {
+        self.backup_error.take()
+    }
+
+    fn backups(&self) -> Vec<PathBuf> {
+        let file_path = Path::new(&self.file_path);
+        let prefix = self.backup_prefix();
+        let Ok(entries) = fs::read_dir(file_path.parent().unwrap_or(Path::new("."))) else {
+            return Vec::new();
+        };
+
+        let mut backups = Vec::from_iter(entries.flatten().map(|e| e.path()).filter(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".bak"))
+        }));
+        // The timestamp format sorts chronologically
+        backups.sort_unstable_by(|a, b| b.cmp(a));
+        backups
+    }
+
+    fn restore(&mut self, backup: &Path) -> io::Result<Vec<Todo>> {
+        let todos = match self.parse(&read_to_string(backup)?) {
+            Ok(todos) => todos,
+            Err(LoadError::Io(error)) => return Err(error),
+            Err(LoadError::Corrupt(data)) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, data.message))
+            }
+            Err(LoadError::ReadOnly { reason, .. }) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, reason))
+            }
+        };
+        // Forces a backup of the current todos so that restoring can itself be reverted
+        self.last_backup = None;
+        self.save(&todos)?;
+        Ok(todos)
+    }
+
+    fn salvage(&mut self) -> Vec<Todo> {
+        salvage_todos(&read_to_string(&self.file_path).unwrap_or_default())
+    }
+}
@@ -0,0 +1,733 @@
+use chrono::{DateTime, Local};
+use rusqlite::{
+    params, params_from_iter,
+    types::{Type, Value},
+    Connection, Row, Transaction,
+};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::{error::Error, fs::exists, io, str::FromStr};
+use uuid::Uuid;
+
+use crate::{
+    due_date::migrate_due_date_string,
+    priority::Priority,
+    status::Statuses,
+    store::{
+        format::{add_ids, statuses_from_done},
+        json::JsonStore,
+        CorruptData, LoadError, StoreChange, TodoStore,
+    },
+    todo::Todo,
+};
+
+/// Migrates the database to the next version, given the statuses of the todos for the versions
+/// that predate them
+type Migration = fn(&Transaction, &Statuses) -> rusqlite::Result<()>;
+
+/// Schema migrations, the database's `user_version` being the number of migrations applied.
+/// Only ever append to this list, never edit an entry that has been released
+const MIGRATIONS: &[Migration] = &[
+    create_todos_table,
+    type_due_dates,
+    add_priorities,
+    add_tags,
+    add_subtasks,
+    add_links,
+    add_recurrences,
+    add_timestamps,
+    add_statuses,
+    add_lists,
+];
+
+/// Columns holding the fields of the todos, in the order of `todo_values`
+const COLUMNS: &[&str] = &[
+    "title",
+    "description",
+    "due_date",
+    "status",
+    "priority",
+    "tags",
+    "children",
+    "collapsed",
+    "uuid",
+    "blocked_by",
+    "recurrence",
+    "completions",
+    "created_at",
+    "updated_at",
+    "completed_at",
+    "list",
+];
+
+fn create_todos_table(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE todos (
+            id INTEGER PRIMARY KEY,
+            position INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL,
+            due_date TEXT NOT NULL,
+            done INTEGER NOT NULL
+        );
+        CREATE INDEX todos_position ON todos(position);",
+    )
+}
+
+/// Due dates used to be free-form strings, they are now nullable and always parsable
+fn type_due_dates(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE todos ADD COLUMN due TEXT")?;
+    let rows = {
+        let mut statement = tx.prepare("SELECT id, due_date, description FROM todos")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get(2)?))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<(i64, String, String)>>>()?
+    };
+    for (id, due_date, mut description) in rows {
+        let due = migrate_due_date_string(&due_date, &mut description).map(|d| d.to_string());
+        tx.execute(
+            "UPDATE todos SET due = ?1, description = ?2 WHERE id = ?3",
+            params![due, description, id],
+        )?;
+    }
+    tx.execute_batch(
+        "ALTER TABLE todos DROP COLUMN due_date;
+        ALTER TABLE todos RENAME COLUMN due TO due_date;",
+    )
+}
+
+pub struct SqliteStore {
+    connection: Connection,
+    db_path: String,
+    /// Todos file of the JSON store, imported once when the database is created
+    json_path: String,
+    /// Set while the JSON todos can't be imported, the errors and their fixes then being about
+    /// the JSON file rather than the database
+    import_failed: bool,
+    /// Statuses given to the todos of the databases and files that predate them
+    statuses: Statuses,
+}
+
+fn to_io_error(error: rusqlite::Error) -> io::Error {
+    io::Error::other(error)
+}
+
+fn add_priorities(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE todos ADD COLUMN priority TEXT NOT NULL DEFAULT 'none'")
+}
+
+/// Tags are stored as a JSON array, as they are only ever read along with their todo
+fn add_tags(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE todos ADD COLUMN tags TEXT NOT NULL DEFAULT '[]'")
+}
+
+/// Subtasks are stored as a JSON tree in their top-level todo, which is the unit the store
+/// changes are made on
+fn add_subtasks(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE todos ADD COLUMN children TEXT NOT NULL DEFAULT '[]';
+        ALTER TABLE todos ADD COLUMN collapsed INTEGER NOT NULL DEFAULT 0;",
+    )
+}
+
+/// The `id` column being the row's, the todos' own ids go in `uuid`. Subtasks get theirs in the
+/// JSON tree of their top-level todo
+fn add_links(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE todos ADD COLUMN uuid TEXT NOT NULL DEFAULT '';
+        ALTER TABLE todos ADD COLUMN blocked_by TEXT NOT NULL DEFAULT '[]';",
+    )?;
+    let rows = {
+        let mut statement = tx.prepare("SELECT id, children FROM todos")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<Vec<(i64, String)>>>()?
+    };
+    for (id, children) in rows {
+        let mut children = serde_json::from_str(&children).unwrap_or_else(|_| json!([]));
+        add_ids(&mut children);
+        tx.execute(
+            "UPDATE todos SET uuid = ?1, children = ?2 WHERE id = ?3",
+            params![Uuid::new_v4().to_string(), children.to_string(), id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Subtasks stored before don't need to be migrated, these fields being optional in JSON
+fn add_recurrences(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE todos ADD COLUMN recurrence TEXT;
+        ALTER TABLE todos ADD COLUMN completions TEXT NOT NULL DEFAULT '[]';",
+    )
+}
+
+fn add_timestamps(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE todos ADD COLUMN created_at TEXT;
+        ALTER TABLE todos ADD COLUMN updated_at TEXT;
+        ALTER TABLE todos ADD COLUMN completed_at TEXT;",
+    )
+}
+
+/// The subtasks' statuses are migrated in the JSON tree of their top-level todo
+fn add_statuses(tx: &Transaction, statuses: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE todos ADD COLUMN status TEXT NOT NULL DEFAULT ''")?;
+    tx.execute(
+        "UPDATE todos SET status = CASE WHEN done != 0 THEN ?1 ELSE ?2 END",
+        params![statuses.done(), statuses.initial()],
+    )?;
+    tx.execute_batch("ALTER TABLE todos DROP COLUMN done")?;
+    let rows = {
+        let mut statement = tx.prepare("SELECT id, children FROM todos")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<Vec<(i64, String)>>>()?
+    };
+    for (id, children) in rows {
+        let mut children = serde_json::from_str(&children).unwrap_or_else(|_| json!([]));
+        statuses_from_done(&mut children, statuses);
+        tx.execute(
+            "UPDATE todos SET children = ?1 WHERE id = ?2",
+            params![children.to_string(), id],
+        )?;
+    }
+    Ok(())
+}
+
+fn add_lists(tx: &Transaction, _: &Statuses) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE todos ADD COLUMN list TEXT NOT NULL DEFAULT 'inbox'")
+}
+
+/// Fails the reading of the column, so that a todo that can't be read is reported as corrupt
+/// rather than read with defaults in place of what it held
+fn conversion_error(idx: usize, error: impl Into<Box<dyn Error + Send + Sync>>) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, error.into())
+}
+
+fn get_json<T: DeserializeOwned>(row: &Row, idx: usize) -> rusqlite::Result<T> {
+    serde_json::from_str(&row.get::<_, String>(idx)?).map_err(|error| conversion_error(idx, error))
+}
+
+fn get_parsed<T: FromStr<Err = String>>(row: &Row, idx: usize) -> rusqlite::Result<Option<T>> {
+    row.get::<_, Option<String>>(idx)?
+        .map(|value| value.parse().map_err(|error| conversion_error(idx, error)))
+        .transpose()
+}
+
+fn get_timestamp(row: &Row, idx: usize) -> rusqlite::Result<Option<DateTime<Local>>> {
+    row.get::<_, Option<String>>(idx)?
+        .map(|timestamp| {
+            DateTime::parse_from_rfc3339(&timestamp)
+                .map(|timestamp| timestamp.with_timezone(&Local))
+                .map_err(|error| conversion_error(idx, error))
+        })
+        .transpose()
+}
+
+fn todo_from_row(row: &Row) -> rusqlite::Result<Todo> {
+    let priority = row.get::<_, String>(4)?;
+    Ok(Todo {
+        title: row.get(0)?,
+        description: row.get(1)?,
+        due_date: get_parsed(row, 2)?,
+        status: row.get(3)?,
+        priority: Priority::parse(&priority)
+            .ok_or_else(|| conversion_error(4, format!("unknown priority '{priority}'")))?,
+        tags: get_json(row, 5)?,
+        children: get_json(row, 6)?,
+        collapsed: row.get(7)?,
+        id: Uuid::parse_str(&row.get::<_, String>(8)?)
+            .map_err(|error| conversion_error(8, error))?,
+        blocked_by: get_json(row, 9)?,
+        recurrence: get_parsed(row, 10)?,
+        completions: get_json(row, 11)?,
+        created_at: get_timestamp(row, 12)?,
+        updated_at: get_timestamp(row, 13)?,
+        completed_at: get_timestamp(row, 14)?,
+        list: row.get(15)?,
+    })
+}
+
+/// Where the todo of the row stopped making sense, its position standing for the line of a file
+fn corrupt_row(row: &Row, error: rusqlite::Error) -> LoadError {
+    let (idx, cause) = match error {
+        rusqlite::Error::FromSqlConversionFailure(idx, _, cause) => (idx, cause.to_string()),
+        rusqlite::Error::InvalidColumnType(idx, _, kind) => (idx, format!("unexpected {kind}")),
+        error => return LoadError::Io(to_io_error(error)),
+    };
+    let position = row.get::<_, usize>(COLUMNS.len()).unwrap_or_default();
+    LoadError::Corrupt(CorruptData {
+        message: format!(
+            "the {} of the todo at position {position} can't be read: {cause}",
+            COLUMNS[idx]
+        ),
+        line: position + 1,
+        column: 1,
+        excerpt: row
+            .get::<_, Option<String>>(idx)
+            .ok()
+            .flatten()
+            .unwrap_or_default(),
+    })
+}
+
+fn todo_values(todo: &Todo) -> Vec<Value> {
+    vec![
+        Value::from(todo.title.clone()),
+        Value::from(todo.description.clone()),
+        Value::from(todo.due_date.map(|due_date| due_date.to_string())),
+        Value::from(todo.status.clone()),
+        Value::from(String::from(todo.priority.as_str())),
+        Value::from(serde_json::to_string(&todo.tags).unwrap_or_default()),
+        Value::from(serde_json::to_string(&todo.children).unwrap_or_default()),
+        Value::from(todo.collapsed),
+        Value::from(todo.id.to_string()),
+        Value::from(serde_json::to_string(&todo.blocked_by).unwrap_or_default()),
+        Value::from(
+            todo.recurrence
+                .as_ref()
+                .map(|recurrence| recurrence.to_string()),
+        ),
+        Value::from(serde_json::to_string(&todo.completions).unwrap_or_default()),
+        Value::from(todo.created_at.map(|timestamp| timestamp.to_rfc3339())),
+        Value::from(todo.updated_at.map(|timestamp| timestamp.to_rfc3339())),
+        Value::from(todo.completed_at.map(|timestamp| timestamp.to_rfc3339())),
+        Value::from(todo.list.clone()),
+    ]
+}
+
+fn insert_todo(tx: &Transaction, position: usize, todo: &Todo) -> rusqlite::Result<()> {
+    let placeholders = Vec::from_iter((1..=COLUMNS.len() + 1).map(|i| format!("?{i}")));
+    let mut values = vec![Value::from(position as i64)];
+    values.extend(todo_values(todo));
+    tx.execute(
+        &format!(
+            "INSERT INTO todos (position, {}) VALUES ({})",
+            COLUMNS.join(", "),
+            placeholders.join(", ")
+        ),
+        params_from_iter(values),
+    )?;
+    Ok(())
+}
+
+fn update_todo(tx: &Transaction, position: usize, todo: &Todo) -> rusqlite::Result<()> {
+    let assignments = Vec::from_iter(
+        COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{column} = ?{}", i + 1)),
+    );
+    let mut values = todo_values(todo);
+    values.push(Value::from(position as i64));
+    tx.execute(
+        &format!(
+            "UPDATE todos SET {} WHERE position = ?{}",
+            assignments.join(", "),
+            COLUMNS.len() + 1
+        ),
+        params_from_iter(values),
+    )?;
+    Ok(())
+}
+
+fn write_todos(tx: &Transaction, todos: &[Todo]) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM todos", [])?;
+    for (position, todo) in todos.iter().enumerate() {
+        insert_todo(tx, position, todo)?;
+    }
+    Ok(())
+}
+
+impl SqliteStore {
+    pub fn open(db_path: String, json_path: String, statuses: Statuses) -> io::Result<Self> {
+        Ok(Self {
+            connection: Connection::open(&db_path).map_err(to_io_error)?,
+            db_path,
+            json_path,
+            import_failed: false,
+            statuses,
+        })
+    }
+
+    fn get_version(&self) -> rusqlite::Result<usize> {
+        self.connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+    }
+
+    /// Applies the migrations the database is missing, then replaces its todos with the given
+    /// ones. It all happens in one transaction, the version only being bumped once the todos are
+    /// in, so that nothing is kept of a migration or an import that fails
+    fn migrate(&mut self, todos: Option<&[Todo]>) -> rusqlite::Result<()> {
+        let version = self.get_version()?;
+        let tx = self.connection.transaction()?;
+        for migration in MIGRATIONS.iter().skip(version) {
+            migration(&tx, &self.statuses)?;
+        }
+        if let Some(todos) = todos {
+            write_todos(&tx, todos)?;
+        }
+        tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+        tx.commit()
+    }
+
+    /// Reads the todos of the JSON store, to be imported in a new database. Its errors are the
+    /// JSON store's, so that a corrupt or newer file can be recovered the same way
+    fn read_json_todos(&mut self) -> Result<Option<Vec<Todo>>, LoadError> {
+        if !exists(&self.json_path).unwrap_or(false) {
+            return Ok(None);
+        }
+        let todos = JsonStore::new(self.json_path.clone(), self.statuses.clone()).load();
+        self.import_failed = matches!(
+            todos,
+            Err(LoadError::Corrupt(_) | LoadError::ReadOnly { .. })
+        );
+        todos.map(Some)
+    }
+
+    /// Reads the todos, along with the error of each row that can't be read. The position of the
+    /// rows comes after the columns of the todos
+    fn read_rows(&self) -> rusqlite::Result<Vec<Result<Todo, LoadError>>> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {}, position FROM todos ORDER BY position",
+            COLUMNS.join(", ")
+        ))?;
+        let mut rows = statement.query([])?;
+        let mut todos = Vec::new();
+        while let Some(row) = rows.next()? {
+            todos.push(todo_from_row(row).map_err(|error| corrupt_row(row, error)));
+        }
+        Ok(todos)
+    }
+
+    /// Reads the todos, the first row that can't be read making the database corrupt
+    fn read_todos(&self) -> Result<Vec<Todo>, LoadError> {
+        self.read_rows().map_err(to_io_error)?.into_iter().collect()
+    }
+
+    fn apply_change(&mut self, change: StoreChange, todos: &[Todo]) -> rusqlite::Result<()> {
+        let tx = self.connection.transaction()?;
+        match change {
+            StoreChange::Added(idx) => {
+                if let Some(todo) = todos.get(idx) {
+                    tx.execute(
+                        "UPDATE todos SET position = position + 1 WHERE position >= ?1",
+                        [idx],
+                    )?;
+                    insert_todo(&tx, idx, todo)?;
+                }
+            }
+            StoreChange::Edited(idx) => {
+                if let Some(todo) = todos.get(idx) {
+                    update_todo(&tx, idx, todo)?;
+                }
+            }
+            StoreChange::Deleted(idx) => {
+                tx.execute("DELETE FROM todos WHERE position = ?1", [idx])?;
+                tx.execute(
+                    "UPDATE todos SET position = position - 1 WHERE position > ?1",
+                    [idx],
+                )?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Writes the changes in one transaction, only rewriting the rows of the todos they added or
+    /// edited and moving the others to where the changes left them
+    fn apply_changes(&mut self, changes: &[StoreChange], todos: &[Todo]) -> rusqlite::Result<()> {
+        let nb_added = changes
+            .iter()
+            .filter(|change| matches!(change, StoreChange::Added(_)))
+            .count();
+        let nb_deleted = changes
+            .iter()
+            .filter(|change| matches!(change, StoreChange::Deleted(_)))
+            .count();
+        // Position of each todo's row before the changes, None for the rows written anew
+        let mut rows =
+            Vec::from_iter((0..(todos.len() + nb_deleted).saturating_sub(nb_added)).map(Some));
+        let mut dropped = Vec::new();
+        for change in changes {
+            match *change {
+                StoreChange::Added(idx) if idx <= rows.len() => rows.insert(idx, None),
+                StoreChange::Edited(idx) if idx < rows.len() => dropped.extend(rows[idx].take()),
+                StoreChange::Deleted(idx) if idx < rows.len() => dropped.extend(rows.remove(idx)),
+                _ => {}
+            }
+        }
+
+        let tx = self.connection.transaction()?;
+        if rows.len() != todos.len() {
+            write_todos(&tx, todos)?;
+            return tx.commit();
+        }
+        for position in dropped {
+            tx.execute("DELETE FROM todos WHERE position = ?1", [position])?;
+        }
+        // The moved rows go through negative positions, so that none of them is ever taken for
+        // one that is still to be moved
+        for (position, row) in rows.iter().enumerate() {
+            if let Some(row) = row.filter(|&row| row != position) {
+                tx.execute(
+                    "UPDATE todos SET position = ?1 WHERE position = ?2",
+                    params![-(position as i64) - 1, row],
+                )?;
+            }
+        }
+        tx.execute(
+            "UPDATE todos SET position = -position - 1 WHERE position < 0",
+            [],
+        )?;
+        for (position, todo) in todos.iter().enumerate() {
+            if rows[position].is_none() {
+                insert_todo(&tx, position, todo)?;
+            }
+        }
+        tx.commit()
+    }
+}
+
+impl TodoStore for SqliteStore {
+    fn load(&mut self) -> Result<Vec<Todo>, LoadError> {
+        // Reads the JSON todos before migrating, so that a failed import is retried next time
+        let imported = match self.get_version().map_err(to_io_error)? {
+            0 => self.read_json_todos()?,
+            _ => None,
+        };
+        self.migrate(imported.as_deref()).map_err(to_io_error)?;
+        self.read_todos()
+    }
+
+    /// Saving the salvaged todos of a failed import creates the database in their place
+    fn save(&mut self, todos: &[Todo]) -> io::Result<()> {
+        self.migrate(Some(todos)).map_err(to_io_error)?;
+        self.import_failed = false;
+        Ok(())
+    }
+
+    fn apply(&mut self, change: StoreChange, todos: &[Todo]) -> io::Result<()> {
+        self.apply_change(change, todos).map_err(to_io_error)
+    }
+
+    fn apply_all(&mut self, changes: &[StoreChange], todos: &[Todo]) -> io::Result<()> {
+        self.apply_changes(changes, todos).map_err(to_io_error)
+    }
+
+    fn location(&self) -> String {
+        match self.import_failed {
+            true => self.json_path.clone(),
+            false => self.db_path.clone(),
+        }
+    }
+
+    fn salvage(&mut self) -> Vec<Todo> {
+        match self.import_failed {
+            true => JsonStore::new(self.json_path.clone(), self.statuses.clone()).salvage(),
+            false => Vec::from_iter(self.read_rows().unwrap_or_default().into_iter().flatten()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::format::{decode, DecodeError};
+
+    fn decode_ok(contents: &str) -> Vec<Todo> {
+        match decode(contents, &Statuses::default()) {
+            Ok(todos) => todos,
+            Err(DecodeError::Invalid(error)) => panic!("invalid fixture: {error}"),
+            Err(DecodeError::Newer { version, .. }) => panic!("fixture of newer version {version}"),
+        }
+    }
+
+    /// Store of a database kept in memory, with no JSON todos to import
+    fn memory_store() -> SqliteStore {
+        let json_path = String::from("/nonexistent/todo-tui/todos.json");
+        SqliteStore::open(String::from(":memory:"), json_path, Statuses::default()).unwrap()
+    }
+
+    /// Store of a database left at an old version, holding the todos written by the first one
+    fn store_at_version(version: usize) -> SqliteStore {
+        let mut store = memory_store();
+        let statuses = Statuses::default();
+        let tx = store.connection.transaction().unwrap();
+        create_todos_table(&tx, &statuses).unwrap();
+        tx.execute_batch(
+            "INSERT INTO todos (position, title, description, due_date, done) VALUES
+                (0, 'call', '', '2024-12-31 18:30', 1),
+                (1, 'read', 'a book', 'someday', 0);",
+        )
+        .unwrap();
+        for migration in &MIGRATIONS[1..version] {
+            migration(&tx, &statuses).unwrap();
+        }
+        tx.pragma_update(None, "user_version", version).unwrap();
+        tx.commit().unwrap();
+        store
+    }
+
+    /// Directory of the test's todos.json, which is written with the contents when given
+    fn json_dir(name: &str, contents: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("todo-tui-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("todos.json"), contents).unwrap();
+        dir
+    }
+
+    #[test]
+    fn every_old_version_is_migrated() {
+        for version in 1..MIGRATIONS.len() {
+            let mut store = store_at_version(version);
+            let todos = match store.load() {
+                Ok(todos) => todos,
+                Err(_) => panic!("version {version} couldn't be migrated"),
+            };
+            assert_eq!(store.get_version().unwrap(), MIGRATIONS.len());
+            assert_eq!(todos.len(), 2, "version {version}");
+            assert_eq!(todos[0].title, "call");
+            assert_eq!(todos[0].status, "done");
+            assert_eq!(todos[1].status, "todo");
+            assert_eq!(
+                todos[0].due_date.map(|due_date| due_date.to_string()),
+                Some(String::from("2024-12-31 18:30"))
+            );
+            assert!(todos[1].due_date.is_none());
+            assert_eq!(todos[1].description, "a book\n\nDue date: someday");
+            for todo in &todos {
+                assert_eq!(todo.priority, Priority::None);
+                assert!(todo.tags.is_empty() && todo.children.is_empty());
+                assert!(todo.blocked_by.is_empty() && todo.recurrence.is_none());
+                assert_eq!(todo.list, "inbox");
+            }
+            assert_ne!(todos[0].id, todos[1].id);
+        }
+    }
+
+    #[test]
+    fn a_new_database_imports_the_json_todos() {
+        let dir = json_dir(
+            "import",
+            r#"{"format_version": 2, "todos": [
+                {"title": "milk", "description": "", "due_date": "2024-12-31", "done": true},
+                {"title": "bread", "description": "", "due_date": "", "done": false}
+            ]}"#,
+        );
+        let json_path = dir.join("todos.json").to_string_lossy().into_owned();
+        let mut store =
+            SqliteStore::open(String::from(":memory:"), json_path, Statuses::default()).unwrap();
+        let todos = match store.load() {
+            Ok(todos) => todos,
+            Err(_) => panic!("the JSON todos couldn't be imported"),
+        };
+        assert_eq!(store.get_version().unwrap(), MIGRATIONS.len());
+        assert_eq!(
+            Vec::from_iter(todos.iter().map(|todo| todo.title.as_str())),
+            ["milk", "bread"]
+        );
+        assert_eq!(todos[0].status, "done");
+        assert_eq!(
+            todos[0].due_date.map(|due_date| due_date.to_string()),
+            Some(String::from("2024-12-31"))
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn a_failed_import_leaves_the_database_new() {
+        let dir = json_dir("failed-import", r#"[{"title": "milk""#);
+        let json_path = dir.join("todos.json").to_string_lossy().into_owned();
+        let mut store = SqliteStore::open(
+            String::from(":memory:"),
+            json_path.clone(),
+            Statuses::default(),
+        )
+        .unwrap();
+        assert!(matches!(store.load(), Err(LoadError::Corrupt(_))));
+        assert_eq!(store.get_version().unwrap(), 0);
+        assert_eq!(store.location(), json_path);
+
+        std::fs::write(
+            &json_path,
+            r#"[{"title": "milk", "description": "", "due_date": "", "done": false}]"#,
+        )
+        .unwrap();
+        assert!(matches!(store.load(), Ok(todos) if todos.len() == 1));
+        assert_eq!(store.get_version().unwrap(), MIGRATIONS.len());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn a_row_that_cant_be_read_is_corrupt() {
+        let mut store = memory_store();
+        assert!(store.load().is_ok());
+        let todos = decode_ok(
+            r#"[
+                {"title": "milk", "description": "", "due_date": "", "done": false},
+                {"title": "bread", "description": "", "due_date": "", "done": false}
+            ]"#,
+        );
+        store.save(&todos).unwrap();
+        store
+            .connection
+            .execute("UPDATE todos SET tags = '[oops' WHERE position = 1", [])
+            .unwrap();
+
+        match store.load() {
+            Err(LoadError::Corrupt(data)) => {
+                assert!(data.message.contains("tags"), "{}", data.message);
+                assert_eq!(data.line, 2);
+                assert_eq!(data.excerpt, "[oops");
+            }
+            _ => panic!("the corrupt row was read"),
+        }
+        let salvaged = store.salvage();
+        assert_eq!(salvaged.len(), 1);
+        assert_eq!(salvaged[0].title, "milk");
+    }
+
+    #[test]
+    fn changes_are_applied_together() {
+        let all = decode_ok(
+            r#"[
+                {"title": "a", "description": "", "due_date": "", "done": false},
+                {"title": "b", "description": "", "due_date": "", "done": false},
+                {"title": "c", "description": "", "due_date": "", "done": false},
+                {"title": "d", "description": "", "due_date": "", "done": false},
+                {"title": "x", "description": "", "due_date": "", "done": false},
+                {"title": "e", "description": "", "due_date": "", "done": false}
+            ]"#,
+        );
+        let mut store = memory_store();
+        assert!(store.load().is_ok());
+        store.save(&all[..4]).unwrap();
+
+        // [a, b, c, d] -> [b, c, d] -> [b, c, x, d] -> [B, c, x, d] -> [B, c, x, d, e]
+        // -> [B, x, d, e]
+        let mut edited = all[1].clone();
+        edited.title = String::from("B");
+        let todos = [edited, all[4].clone(), all[3].clone(), all[5].clone()];
+        let changes = [
+            StoreChange::Deleted(0),
+            StoreChange::Added(2),
+            StoreChange::Edited(0),
+            StoreChange::Added(4),
+            StoreChange::Deleted(1),
+        ];
+        store.apply_all(&changes, &todos).unwrap();
+
+        let stored = match store.load() {
+            Ok(stored) => stored,
+            Err(_) => panic!("the changed todos couldn't be read"),
+        };
+        assert_eq!(
+            Vec::from_iter(stored.iter().map(|todo| todo.title.as_str())),
+            ["B", "x", "d", "e"]
+        );
+        assert!(stored
+            .iter()
+            .zip(&todos)
+            .all(|(stored, todo)| stored.id == todo.id));
+    }
+}
@@ -0,0 +1,45 @@
+use ratatui::{
+    style::{Color, Style},
+    text::Span,
+};
+
+const TAG_COLORS: &[Color] = &[
+    Color::Blue,
+    Color::Green,
+    Color::Cyan,
+    Color::Yellow,
+    Color::LightMagenta,
+    Color::LightBlue,
+    Color::LightGreen,
+];
+
+/// Splits what the user typed into tags, which can be separated by commas or spaces and
+/// start with a `#`
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|tag| tag.trim_start_matches('#'))
+        .filter(|tag| !tag.is_empty())
+    {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(String::from(tag));
+        }
+    }
+    tags
+}
+
+/// The same tag always gets the same color, so that it can be recognized at a glance
+pub fn tag_color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(0usize, |hash, b| {
+        hash.wrapping_mul(31).wrapping_add(b as usize)
+    });
+    TAG_COLORS[hash % TAG_COLORS.len()]
+}
+
+pub fn tag_chip(tag: &str) -> Span<'static> {
+    Span::styled(
+        format!(" #{tag} "),
+        Style::new().fg(Color::Black).bg(tag_color(tag)),
+    )
+}
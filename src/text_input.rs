@@ -0,0 +1,310 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+};
+use std::ops::Range;
+
+const SELECTION_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Gray);
+
+/// Text being written in a field of the create screen, edited at a cursor rather than only at
+/// its end. Positions are byte offsets that always fall on character boundaries
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+    /// Where the selection started, the cursor being its other end
+    anchor: Option<usize>,
+    /// Length in bytes the text can't go over
+    max_len: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Adds the character to the last span if it has the same style, so that spans aren't one
+/// character each
+fn push_char(spans: &mut Vec<Span<'static>>, c: char, style: Style) {
+    match spans.last_mut() {
+        Some(span) if span.style == style => span.content.to_mut().push(c),
+        _ => spans.push(Span::styled(String::from(c), style)),
+    }
+}
+
+impl TextInput {
+    pub fn new(max_len: usize) -> Self {
+        TextInput {
+            text: String::new(),
+            cursor: 0,
+            anchor: None,
+            max_len,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Replaces the text, the cursor going to its end
+    pub fn set(&mut self, text: &str) {
+        self.text = String::from(text);
+        self.cursor = self.text.len();
+        self.anchor = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set("");
+    }
+
+    /// The selected part of the text, if any
+    pub fn selection(&self) -> Option<Range<usize>> {
+        self.anchor
+            .filter(|&anchor| anchor != self.cursor)
+            .map(|anchor| anchor.min(self.cursor)..anchor.max(self.cursor))
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.text.len();
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some(range) => {
+                self.cursor = range.start;
+                self.text.replace_range(range, "");
+                self.anchor = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Writes the text at the cursor in place of the selection, unless it would make the text
+    /// too long
+    pub fn insert_str(&mut self, s: &str) {
+        let selected = self.selection().map_or(0, |range| range.len());
+        if self.text.len() - selected + s.len() > self.max_len {
+            return;
+        }
+        self.delete_selection();
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+        self.anchor = None;
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.insert_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Deletes the selection, or the character before the cursor
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() {
+            if let Some(previous) = self.previous_char(self.cursor) {
+                self.text.replace_range(previous..self.cursor, "");
+                self.cursor = previous;
+            }
+        }
+    }
+
+    /// Deletes the selection, or the character after the cursor
+    pub fn delete(&mut self) {
+        if !self.delete_selection() {
+            if let Some(next) = self.next_char(self.cursor) {
+                self.text.replace_range(self.cursor..next, "");
+            }
+        }
+    }
+
+    fn previous_char(&self, position: usize) -> Option<usize> {
+        self.text[..position]
+            .char_indices()
+            .next_back()
+            .map(|(idx, _)| idx)
+    }
+
+    fn next_char(&self, position: usize) -> Option<usize> {
+        self.text[position..]
+            .chars()
+            .next()
+            .map(|c| position + c.len_utf8())
+    }
+
+    /// Start of the word before the position, skipping the spaces and punctuation in between
+    fn previous_word(&self, position: usize) -> usize {
+        let before = &self.text[..position];
+        let end = before.trim_end_matches(|c| !is_word_char(c)).len();
+        before[..end].trim_end_matches(is_word_char).len()
+    }
+
+    /// End of the word after the position, skipping the spaces and punctuation in between
+    fn next_word(&self, position: usize) -> usize {
+        let after = &self.text[position..];
+        let start = after.len() - after.trim_start_matches(|c| !is_word_char(c)).len();
+        let word = after[start..].trim_start_matches(is_word_char);
+        self.text.len() - word.len()
+    }
+
+    /// Moves the cursor, extending the selection when `select` is set and dropping it otherwise
+    fn move_to(&mut self, position: usize, select: bool) {
+        match select {
+            true => {
+                self.anchor.get_or_insert(self.cursor);
+            }
+            false => self.anchor = None,
+        }
+        self.cursor = position;
+    }
+
+    /// Moving without selecting from a selection goes to its edge rather than past it
+    pub fn move_left(&mut self, select: bool) {
+        let position = match (self.selection(), select) {
+            (Some(range), false) => range.start,
+            _ => self.previous_char(self.cursor).unwrap_or(0),
+        };
+        self.move_to(position, select);
+    }
+
+    pub fn move_right(&mut self, select: bool) {
+        let position = match (self.selection(), select) {
+            (Some(range), false) => range.end,
+            _ => self.next_char(self.cursor).unwrap_or(self.text.len()),
+        };
+        self.move_to(position, select);
+    }
+
+    pub fn move_word_left(&mut self, select: bool) {
+        self.move_to(self.previous_word(self.cursor), select);
+    }
+
+    pub fn move_word_right(&mut self, select: bool) {
+        self.move_to(self.next_word(self.cursor), select);
+    }
+
+    /// Goes to the start of the line the cursor is on
+    pub fn move_home(&mut self, select: bool) {
+        let start = self.text[..self.cursor]
+            .rfind('\n')
+            .map_or(0, |idx| idx + 1);
+        self.move_to(start, select);
+    }
+
+    /// Goes to the end of the line the cursor is on
+    pub fn move_end(&mut self, select: bool) {
+        let end = self.text[self.cursor..]
+            .find('\n')
+            .map_or(self.text.len(), |idx| self.cursor + idx);
+        self.move_to(end, select);
+    }
+
+    /// Lines of the text in the given style, the selection being highlighted and the cursor
+    /// shown as a reversed cell when `focused`
+    pub fn to_text(&self, style: Style, focused: bool) -> Text<'static> {
+        let selection = self.selection().filter(|_| focused);
+        let cursor_style = style.reversed();
+        let mut lines = Vec::new();
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        for (idx, c) in self.text.char_indices() {
+            let is_cursor = focused && idx == self.cursor;
+            let c_style = match selection.as_ref() {
+                _ if is_cursor => cursor_style,
+                Some(range) if range.contains(&idx) => style.patch(SELECTION_STYLE),
+                _ => style,
+            };
+            if c == '\n' {
+                if is_cursor {
+                    push_char(&mut spans, ' ', cursor_style);
+                }
+                lines.push(Line::from(std::mem::take(&mut spans)));
+            } else {
+                push_char(&mut spans, c, c_style);
+            }
+        }
+        if focused && self.cursor == self.text.len() {
+            push_char(&mut spans, ' ', cursor_style);
+        }
+        lines.push(Line::from(spans));
+        Text::from(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Input holding the text, the cursor at its end
+    fn input(text: &str) -> TextInput {
+        let mut input = TextInput::new(100);
+        input.set(text);
+        input
+    }
+
+    #[test]
+    fn edits_multibyte_characters_whole() {
+        let mut input = input("café");
+        input.move_left(false);
+        assert_eq!(input.cursor, 3);
+        input.insert_char('ñ');
+        assert_eq!(input.as_str(), "cafñé");
+        input.delete();
+        input.backspace();
+        assert_eq!(input.as_str(), "caf");
+        input.insert_str("日本");
+        input.move_left(true);
+        assert_eq!(input.selection(), Some(6..9));
+    }
+
+    #[test]
+    fn word_jumps_skip_punctuation() {
+        let mut input = input("buy milk, eggs... & bread");
+        input.move_word_left(false);
+        assert_eq!(input.cursor, 20);
+        input.move_word_left(false);
+        assert_eq!(input.cursor, 10);
+        input.move_word_left(false);
+        assert_eq!(input.cursor, 4);
+        input.move_word_right(false);
+        assert_eq!(input.cursor, 8);
+        input.move_word_right(false);
+        assert_eq!(input.cursor, 14);
+        input.move_word_right(false);
+        assert_eq!(input.cursor, 25);
+        input.move_word_right(false);
+        assert_eq!(input.cursor, 25);
+    }
+
+    #[test]
+    fn typing_replaces_the_selection() {
+        let mut input = input("buy milk");
+        input.move_word_left(true);
+        assert_eq!(input.selection(), Some(4..8));
+        input.insert_char('é');
+        assert_eq!(input.as_str(), "buy é");
+        assert_eq!(input.selection(), None);
+
+        input.select_all();
+        input.backspace();
+        assert_eq!(input.as_str(), "");
+    }
+
+    #[test]
+    fn text_cant_go_over_max_len() {
+        let mut input = TextInput::new(4);
+        input.insert_str("abc");
+        input.insert_char('é');
+        assert_eq!(input.as_str(), "abc");
+        input.insert_char('d');
+        assert_eq!(input.as_str(), "abcd");
+        input.insert_char('e');
+        assert_eq!(input.as_str(), "abcd");
+
+        // Replacing the selection only counts what is left of the text
+        input.move_left(true);
+        input.move_left(true);
+        input.insert_char('é');
+        assert_eq!(input.as_str(), "abé");
+    }
+}
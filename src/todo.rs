@@ -1,131 +1,2226 @@
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeZone};
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+};
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::Write};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+};
 
-use crate::states::States;
+use crate::{
+    archive::Archive,
+    due_date::{recurrence::Recurrence, DueDate, DueStatus},
+    history::{Command, History, Step},
+    priority::Priority,
+    search::{self, FuzzyHit},
+    states::States,
+    status::{StatusKind, Statuses},
+    store::{CorruptData, LoadError, StoreChange, TodoStore},
+    tags::tag_chip,
+    trash::{Trash, TrashEntry},
+};
+use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
+/// Position of a todo in the tree, its index among its siblings at every depth. It changes as
+/// todos are added and deleted, so it never leaves this module where the todos are known by id
+type TodoPath = Vec<usize>;
+
+/// List of the todos that were written before there could be several lists
+pub const DEFAULT_LIST: &str = "inbox";
+
+fn default_list() -> String {
+    String::from(DEFAULT_LIST)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Todo {
+    /// Identifies the todo in the links between todos, which outlive its position
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
     pub title: String,
     pub description: String,
-    pub due_date: String,
-    pub done: bool,
+    pub due_date: Option<DueDate>,
+    /// Name of one of the statuses, which tell whether the todo is done
+    pub status: String,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub children: Vec<Todo>,
+    /// Whether the children are hidden in the list
+    #[serde(default)]
+    pub collapsed: bool,
+    /// Todos that have to be done before this one can be, links to deleted todos being ignored
+    #[serde(default)]
+    pub blocked_by: Vec<Uuid>,
+    /// Todos that repeat are never left done, their due date moving to the next occurrence
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// When a repeating todo was done
+    #[serde(default)]
+    pub completions: Vec<DateTime<Local>>,
+    /// Unknown for the todos created before they were recorded
+    #[serde(default)]
+    pub created_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Local>>,
+    /// When the todo was last done, kept for repeating todos although they are never left done
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Local>>,
+    /// Name of the list the todo is in, subtasks always being in the list of their parent
+    #[serde(default = "default_list")]
+    pub list: String,
+}
+
+/// What the todos are listed by
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// The order in which the todos were added
+    #[default]
+    Manual,
+    DueDate,
+    Priority,
+    Title,
+    /// The order of the statuses
+    Status,
+    Created,
+    Updated,
+    Completed,
+}
+
+impl SortKey {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Manual => "manual",
+            SortKey::DueDate => "due date",
+            SortKey::Priority => "priority",
+            SortKey::Title => "title",
+            SortKey::Status => "status",
+            SortKey::Created => "created",
+            SortKey::Updated => "updated",
+            SortKey::Completed => "completed",
+        }
+    }
+
+    fn next(&self) -> SortKey {
+        match self {
+            SortKey::Manual => SortKey::DueDate,
+            SortKey::DueDate => SortKey::Priority,
+            SortKey::Priority => SortKey::Title,
+            SortKey::Title => SortKey::Status,
+            SortKey::Status => SortKey::Created,
+            SortKey::Created => SortKey::Updated,
+            SortKey::Updated => SortKey::Completed,
+            SortKey::Completed => SortKey::Manual,
+        }
+    }
+
+    /// The direction that makes sense first: the most urgent and the most recent todos first,
+    /// the others in ascending order
+    fn descending_by_default(&self) -> bool {
+        matches!(
+            self,
+            SortKey::Priority | SortKey::Created | SortKey::Updated | SortKey::Completed
+        )
+    }
+}
+
+/// Order in which the todos are listed, siblings being sorted among themselves
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+    /// Whether the done and cancelled todos go after the open ones, whatever the key
+    pub closed_last: bool,
+}
+
+impl Sort {
+    pub fn label(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.key != SortKey::Manual || self.descending {
+            let arrow = if self.descending { "↓" } else { "↑" };
+            parts.push(format!("by {} {arrow}", self.key.label()));
+        }
+        if self.closed_last {
+            parts.push(String::from("done last"));
+        }
+        match parts.is_empty() {
+            true => None,
+            false => Some(parts.join(", ")),
+        }
+    }
+}
+
+/// Orders the values in the direction, todos without a value always going last
+fn cmp_missing_last<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimeField {
+    Created,
+    Updated,
+    Completed,
+}
+
+impl TimeField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimeField::Created => "created",
+            TimeField::Updated => "updated",
+            TimeField::Completed => "completed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Today,
+    ThisWeek,
+    ThisMonth,
+}
+
+/// Only lists the todos created, updated or completed recently
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TimeFilter {
+    pub field: TimeField,
+    pub period: Period,
+}
+
+/// Filters cycled through on the main screen, in that order
+const TIME_FILTERS: &[TimeFilter] = &[
+    TimeFilter {
+        field: TimeField::Created,
+        period: Period::ThisWeek,
+    },
+    TimeFilter {
+        field: TimeField::Updated,
+        period: Period::Today,
+    },
+    TimeFilter {
+        field: TimeField::Updated,
+        period: Period::ThisWeek,
+    },
+    TimeFilter {
+        field: TimeField::Completed,
+        period: Period::Today,
+    },
+    TimeFilter {
+        field: TimeField::Completed,
+        period: Period::ThisWeek,
+    },
+    TimeFilter {
+        field: TimeField::Completed,
+        period: Period::ThisMonth,
+    },
+];
+
+impl Period {
+    /// When the period started, weeks starting on Monday
+    fn start(&self, now: DateTime<Local>) -> DateTime<Local> {
+        let today = now.date_naive();
+        let first_day = match self {
+            Period::Today => Some(today),
+            Period::ThisWeek => {
+                today.checked_sub_days(Days::new(today.weekday().num_days_from_monday().into()))
+            }
+            Period::ThisMonth => today.with_day(1),
+        };
+        first_day
+            .map(|day| day.and_time(NaiveTime::MIN))
+            .and_then(|start| Local.from_local_datetime(&start).earliest())
+            .unwrap_or(now)
+    }
+}
+
+impl TimeFilter {
+    pub fn label(&self) -> String {
+        let period = match self.period {
+            Period::Today => "today",
+            Period::ThisWeek => "this week",
+            Period::ThisMonth => "this month",
+        };
+        format!("{} {period}", self.field.label())
+    }
+
+    fn matches(&self, todo: &Todo, now: DateTime<Local>) -> bool {
+        todo.get_time(self.field)
+            .is_some_and(|time| time >= self.period.start(now))
+    }
+}
+
+impl Todo {
+    /// How close the todo is to its due date, closed todos never being late
+    pub fn due_status(&self, statuses: &Statuses) -> Option<DueStatus> {
+        match statuses.is_closed(&self.status) {
+            true => None,
+            false => self.due_date.map(|due_date| due_date.status(Local::now())),
+        }
+    }
+
+    /// Number of closed descendants, and number of descendants
+    pub fn progress(&self, statuses: &Statuses) -> (usize, usize) {
+        self.children.iter().fold((0, 0), |(closed, total), child| {
+            let (child_closed, child_total) = child.progress(statuses);
+            (
+                closed + child_closed + usize::from(statuses.is_closed(&child.status)),
+                total + child_total + 1,
+            )
+        })
+    }
+
+    /// Puts the todo in the given status of kind done, or logs the completion and moves on to
+    /// the next occurrence if it repeats, its subtasks being reopened for it
+    fn complete(&mut self, now: DateTime<Local>, status: &str, statuses: &Statuses) {
+        self.completed_at = Some(now);
+        self.updated_at = Some(now);
+        match self.recurrence.take() {
+            Some(recurrence) => {
+                // Todos stored before the day of their monthly rule was set get it now
+                let recurrence = recurrence.pinned(self.due_date, now.date_naive());
+                self.completions.push(now);
+                if let Some(next) = recurrence.next_due(self.due_date, now.date_naive()) {
+                    self.due_date = Some(next);
+                }
+                self.recurrence = Some(recurrence);
+                self.status = String::from(statuses.initial());
+                for child in &mut self.children {
+                    child.reopen_recursively(statuses);
+                }
+            }
+            None => self.status = String::from(status),
+        }
+    }
+
+    /// Completes the todo and its open descendants, the cancelled ones staying cancelled
+    fn complete_recursively(&mut self, now: DateTime<Local>, statuses: &Statuses) {
+        for child in &mut self.children {
+            child.complete_recursively(now, statuses);
+        }
+        if !statuses.is_closed(&self.status) {
+            self.complete(now, statuses.done(), statuses);
+        }
+    }
+
+    fn reopen_recursively(&mut self, statuses: &Statuses) {
+        self.status = String::from(statuses.initial());
+        self.completed_at = None;
+        for child in &mut self.children {
+            child.reopen_recursively(statuses);
+        }
+    }
+
+    pub fn get_time(&self, field: TimeField) -> Option<DateTime<Local>> {
+        match field {
+            TimeField::Created => self.created_at,
+            TimeField::Updated => self.updated_at,
+            TimeField::Completed => self.completed_at,
+        }
+    }
+
+    pub fn title_or_placeholder(&self) -> String {
+        match self.title.is_empty() {
+            true => String::from("N/A"),
+            false => self.title.clone(),
+        }
+    }
+}
+
+fn get_in<'a>(todos: &'a [Todo], path: &[usize]) -> Option<&'a Todo> {
+    let (&idx, rest) = path.split_first()?;
+    let todo = todos.get(idx)?;
+    match rest.is_empty() {
+        true => Some(todo),
+        false => get_in(&todo.children, rest),
+    }
+}
+
+fn get_in_mut<'a>(todos: &'a mut [Todo], path: &[usize]) -> Option<&'a mut Todo> {
+    let (&idx, rest) = path.split_first()?;
+    let todo = todos.get_mut(idx)?;
+    match rest.is_empty() {
+        true => Some(todo),
+        false => get_in_mut(&mut todo.children, rest),
+    }
+}
+
+/// Every todo of the tree, parents before their children
+fn flatten<'a>(todos: &'a [Todo], flattened: &mut Vec<&'a Todo>) {
+    for todo in todos {
+        flattened.push(todo);
+        flatten(&todo.children, flattened);
+    }
+}
+
+fn find_path(todos: &[Todo], id: Uuid) -> Option<TodoPath> {
+    todos.iter().enumerate().find_map(|(idx, todo)| {
+        let mut path = match todo.id == id {
+            true => Vec::new(),
+            false => find_path(&todo.children, id)?,
+        };
+        path.insert(0, idx);
+        Some(path)
+    })
+}
+
+fn find_by_id(todos: &[Todo], id: Uuid) -> Option<&Todo> {
+    todos.iter().find_map(|todo| match todo.id == id {
+        true => Some(todo),
+        false => find_by_id(&todo.children, id),
+    })
+}
+
+/// Blockers of the todo that are neither done nor cancelled
+fn open_blockers<'a>(todos: &'a [Todo], statuses: &Statuses, todo: &Todo) -> Vec<&'a Todo> {
+    Vec::from_iter(
+        todo.blocked_by
+            .iter()
+            .filter_map(|&id| find_by_id(todos, id))
+            .filter(|blocker| !statuses.is_closed(&blocker.status)),
+    )
+}
+
+/// Whether the todo of id `from` has to wait on the todo of id `target`, directly or through
+/// other blockers
+fn waits_on(todos: &[Todo], from: Uuid, target: Uuid, visited: &mut HashSet<Uuid>) -> bool {
+    if from == target {
+        return true;
+    }
+    if !visited.insert(from) {
+        return false;
+    }
+    find_by_id(todos, from).is_some_and(|todo| {
+        todo.blocked_by
+            .iter()
+            .any(|&id| waits_on(todos, id, target, visited))
+    })
+}
+
+/// The change to give to the store for a todo, as stores only know about the top-level todos
+/// which contain their subtasks
+fn edited(path: &[usize]) -> Option<StoreChange> {
+    path.first().map(|&idx| StoreChange::Edited(idx))
 }
 
 pub struct Todos {
     todos: Vec<Todo>,
-    file_path: String,
+    store: Box<dyn TodoStore>,
+    statuses: Statuses,
+    /// Set when the stored todos couldn't be read, nothing is written until it is resolved
+    load_error: Option<CorruptData>,
+    /// Todos recovered from the corrupt data, waiting for the user to confirm the salvage
+    salvaged: Option<Vec<Todo>>,
+    /// Set when the todos can be shown but not modified
+    read_only_reason: Option<String>,
+    sort: Sort,
+    /// Only the todos with this tag are listed when set
+    tag_filter: Option<String>,
+    time_filter: Option<TimeFilter>,
+    /// Only the todos matching the search are listed while there is one
+    search: Option<String>,
+    /// List whose todos are shown
+    list: String,
+    /// Lists created during the session, which are only stored once they have todos
+    new_lists: Vec<String>,
+    /// Paths of the todos in the order they are listed, the rows of the UI mapping to it
+    view: Vec<TodoPath>,
+    /// The todos as they were last written, from which the history gets what changes replaced
+    stored: Vec<Todo>,
+    history: History,
+    /// Where the history is saved, also right after a purge so that it doesn't keep the purged
+    /// todos
+    history_path: Option<String>,
+    trash: Trash,
+    archive: Archive,
+    /// Command collecting the changes of the action being run
+    recording: Option<Command>,
+    /// Changes to the todos made by the command being run, written at once when it ends
+    pending: Option<Vec<StoreChange>>,
+    /// Why the command being run can't go on, nothing of it being kept
+    aborted: Option<String>,
+    /// Why a write made along the way failed, until it is shown to the user
+    error: Option<String>,
 }
 
 impl Todos {
-    pub fn new(file_contents: String, file_path: String) -> Todos {
-        Self {
-            todos: serde_json::from_str(&file_contents).unwrap_or_default(),
-            file_path,
+    pub fn new(store: Box<dyn TodoStore>, statuses: Statuses) -> io::Result<Todos> {
+        let mut todos = Self {
+            todos: Vec::new(),
+            store,
+            statuses,
+            load_error: None,
+            salvaged: None,
+            read_only_reason: None,
+            sort: Sort::default(),
+            tag_filter: None,
+            time_filter: None,
+            search: None,
+            list: String::new(),
+            new_lists: Vec::new(),
+            view: Vec::new(),
+            stored: Vec::new(),
+            history: History::default(),
+            history_path: None,
+            trash: Trash::default(),
+            archive: Archive::default(),
+            recording: None,
+            pending: None,
+            aborted: None,
+            error: None,
+        };
+        todos.reload()?;
+        Ok(todos)
+    }
+
+    pub fn reload(&mut self) -> io::Result<()> {
+        match self.store.load() {
+            Ok(todos) => {
+                self.set_todos(todos);
+                self.load_error = None;
+                self.read_only_reason = None;
+            }
+            Err(LoadError::Corrupt(data)) => {
+                self.set_todos(Vec::new());
+                self.load_error = Some(data);
+            }
+            Err(LoadError::ReadOnly { todos, reason }) => {
+                self.set_todos(todos);
+                self.load_error = None;
+                self.read_only_reason = Some(reason);
+            }
+            Err(LoadError::Io(error)) => return Err(error),
+        }
+        Ok(())
+    }
+
+    /// Replaces all the todos, which the history doesn't apply to anymore
+    fn set_todos(&mut self, todos: Vec<Todo>) {
+        self.stored = todos.clone();
+        self.todos = todos;
+        self.history = History::default();
+        if !self.todos.iter().any(|todo| todo.list == self.list)
+            && !self.new_lists.contains(&self.list)
+        {
+            self.list = self
+                .todos
+                .first()
+                .map_or_else(default_list, |todo| todo.list.clone());
+        }
+        self.refresh_view();
+    }
+
+    fn refresh_view(&mut self) {
+        let mut view = Vec::new();
+        self.push_rows(&self.todos, &[], Local::now(), &mut view);
+        self.view = view;
+    }
+
+    fn compare(&self, a: &Todo, b: &Todo) -> Ordering {
+        let descending = self.sort.descending;
+        let closed = match self.sort.closed_last {
+            true => self
+                .statuses
+                .is_closed(&a.status)
+                .cmp(&self.statuses.is_closed(&b.status)),
+            false => Ordering::Equal,
+        };
+        let by_key = match self.sort.key {
+            SortKey::Manual => Ordering::Equal,
+            SortKey::DueDate => cmp_missing_last(
+                a.due_date.map(|due_date| due_date.deadline()),
+                b.due_date.map(|due_date| due_date.deadline()),
+                descending,
+            ),
+            SortKey::Priority => cmp_missing_last(Some(a.priority), Some(b.priority), descending),
+            SortKey::Title => cmp_missing_last(
+                Some(a.title.to_lowercase()),
+                Some(b.title.to_lowercase()),
+                descending,
+            ),
+            SortKey::Status => cmp_missing_last(
+                Some(self.statuses.position(&a.status)),
+                Some(self.statuses.position(&b.status)),
+                descending,
+            ),
+            SortKey::Created => cmp_missing_last(a.created_at, b.created_at, descending),
+            SortKey::Updated => cmp_missing_last(a.updated_at, b.updated_at, descending),
+            SortKey::Completed => cmp_missing_last(a.completed_at, b.completed_at, descending),
+        };
+        closed.then(by_key)
+    }
+
+    /// A todo is listed if it passes the filters, or if one of its descendants does
+    fn matches_filters(&self, todo: &Todo, now: DateTime<Local>) -> bool {
+        let matches_tag = self
+            .tag_filter
+            .as_ref()
+            .is_none_or(|tag| todo.tags.contains(tag));
+        let matches_time = self
+            .time_filter
+            .is_none_or(|filter| filter.matches(todo, now));
+        let matches_search = self.matches_search(todo);
+        (matches_tag && matches_time && matches_search)
+            || todo
+                .children
+                .iter()
+                .any(|child| self.matches_filters(child, now))
+    }
+
+    /// Indices of the siblings that are listed, in the order they are listed in
+    fn listed_indices(
+        &self,
+        siblings: &[Todo],
+        top_level: bool,
+        now: DateTime<Local>,
+    ) -> Vec<usize> {
+        // Subtasks are in the list of their parent, only the top-level todos have to be checked
+        let mut indices = Vec::from_iter((0..siblings.len()).filter(|&idx| {
+            (!top_level || siblings[idx].list == self.list)
+                && self.matches_filters(&siblings[idx], now)
+        }));
+        if self.sort.key == SortKey::Manual && self.sort.descending {
+            indices.reverse();
         }
+        // Stable, so todos that compare equal stay in their manual order
+        indices.sort_by(|&a, &b| self.compare(&siblings[a], &siblings[b]));
+        indices
     }
 
-    pub fn write(&mut self) {
-        match File::create(&self.file_path) {
-            Ok(mut file) => {
-                if let Ok(json_str) = serde_json::to_string_pretty(&self.todos) {
-                    if file.write_all(json_str.as_bytes()).is_err() {
-                        println!("couldn't write to the file '{}'", self.file_path)
+    /// Lists the siblings in order, each followed by its children unless it is collapsed
+    fn push_rows(
+        &self,
+        siblings: &[Todo],
+        parent: &[usize],
+        now: DateTime<Local>,
+        view: &mut Vec<TodoPath>,
+    ) {
+        for idx in self.listed_indices(siblings, parent.is_empty(), now) {
+            let mut path = parent.to_vec();
+            path.push(idx);
+            view.push(path.clone());
+            if !siblings[idx].collapsed {
+                self.push_rows(&siblings[idx].children, &path, now, view);
+            }
+        }
+    }
+
+    /// Id of the todo listed at the given row
+    pub fn get_id(&mut self, row: usize) -> Option<Uuid> {
+        self.view
+            .get(row)
+            .and_then(|path| get_in(&self.todos, path))
+            .map(|todo| todo.id)
+    }
+
+    /// Row at which the todo is listed, if it is
+    pub fn get_row(&mut self, id: Uuid) -> Option<usize> {
+        let path = find_path(&self.todos, id)?;
+        self.view.iter().position(|p| *p == path)
+    }
+
+    /// Id of the todo the given one is a subtask of
+    pub fn get_parent(&mut self, id: Uuid) -> Option<Uuid> {
+        let path = find_path(&self.todos, id)?;
+        let (_, parent) = path.split_last()?;
+        get_in(&self.todos, parent).map(|todo| todo.id)
+    }
+
+    /// Number of todos listed, which is less than the number of todos when they are filtered
+    pub fn get_nb_rows(&mut self) -> usize {
+        self.view.len()
+    }
+
+    /// Names of the lists in the order they were created, along with their number of open todos
+    pub fn get_lists(&mut self) -> Vec<(String, usize)> {
+        let mut lists: Vec<(String, usize)> = Vec::new();
+        for todo in &self.todos {
+            let mut subtree = vec![todo];
+            flatten(&todo.children, &mut subtree);
+            let nb_open = subtree
+                .iter()
+                .filter(|todo| !self.statuses.is_closed(&todo.status))
+                .count();
+            match lists.iter_mut().find(|(name, _)| *name == todo.list) {
+                Some((_, count)) => *count += nb_open,
+                None => lists.push((todo.list.clone(), nb_open)),
+            }
+        }
+        for name in self.new_lists.iter().chain([&self.list]) {
+            if !lists.iter().any(|(list, _)| list == name) {
+                lists.push((name.clone(), 0));
+            }
+        }
+        lists
+    }
+
+    pub fn get_list(&mut self) -> &str {
+        &self.list
+    }
+
+    /// Shows the todos of the next list, or of the previous one when `step` is negative
+    pub fn cycle_list(&mut self, step: isize) {
+        let lists = self.get_lists();
+        if let Some(idx) = lists.iter().position(|(name, _)| *name == self.list) {
+            let next = (idx as isize + step).rem_euclid(lists.len() as isize) as usize;
+            self.list = lists[next].0.clone();
+            self.refresh_view();
+        }
+    }
+
+    /// Creates the list if there is none of that name yet, and shows it
+    pub fn add_list(&mut self, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        if !self.get_lists().iter().any(|(list, _)| list == name) {
+            self.new_lists.push(String::from(name));
+        }
+        self.list = String::from(name);
+        self.refresh_view();
+    }
+
+    /// Moves the todo to the end of the shown list, a subtask leaving its parent to become a
+    /// top-level todo there
+    pub fn move_to_list(&mut self, id: Uuid) {
+        let label = self.describe("move", id) + &format!(" to {}", self.list);
+        self.record(label, |todos| todos.move_to_current_list(id));
+    }
+
+    fn move_to_current_list(&mut self, id: Uuid) {
+        let path = match find_path(&self.todos, id) {
+            Some(path) => path,
+            None => return,
+        };
+        let mut todo = match path.split_last() {
+            Some((&idx, [])) if idx < self.todos.len() && self.todos[idx].list != self.list => {
+                let todo = self.todos.remove(idx);
+                self.write(StoreChange::Deleted(idx));
+                todo
+            }
+            Some((&idx, parent)) if !parent.is_empty() => {
+                let todo = match get_in_mut(&mut self.todos, parent) {
+                    Some(parent_todo) if idx < parent_todo.children.len() => {
+                        parent_todo.updated_at = Some(Local::now());
+                        parent_todo.children.remove(idx)
                     }
+                    _ => return,
+                };
+                if let Some(change) = edited(parent) {
+                    self.write(change);
                 }
+                todo
             }
-            Err(_) => {
-                println!("couldn't open/create to the file '{}'", self.file_path);
+            _ => return,
+        };
+        todo.list = self.list.clone();
+        todo.updated_at = Some(Local::now());
+        self.todos.push(todo);
+        self.write(StoreChange::Added(self.todos.len() - 1));
+    }
+
+    /// Moves the todo past the sibling listed right above or below it. The siblings that are
+    /// filtered out keep their place, so that the order only changes where it can be seen
+    pub fn move_todo(&mut self, id: Uuid, up: bool) -> Result<(), String> {
+        if self.sort.key != SortKey::Manual {
+            return Err(String::from(
+                "todos can only be moved in the manual order, press 's' to go back to it",
+            ));
+        }
+        let path = match find_path(&self.todos, id) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let (idx, parent) = match path.split_last() {
+            Some((&idx, parent)) => (idx, parent),
+            None => return Ok(()),
+        };
+        let neighbour = {
+            let siblings = match parent.is_empty() {
+                true => &self.todos,
+                false => match get_in(&self.todos, parent) {
+                    Some(parent_todo) => &parent_todo.children,
+                    None => return Ok(()),
+                },
+            };
+            let listed = self.listed_indices(siblings, parent.is_empty(), Local::now());
+            let neighbour = listed
+                .iter()
+                .position(|&listed_idx| listed_idx == idx)
+                .and_then(|row| match up {
+                    true => row.checked_sub(1).map(|row| listed[row]),
+                    false => listed.get(row + 1).copied(),
+                });
+            // Done todos listed last stay after the open ones
+            match neighbour {
+                Some(neighbour)
+                    if self.compare(&siblings[idx], &siblings[neighbour]) == Ordering::Equal =>
+                {
+                    neighbour
+                }
+                _ => return Ok(()),
+            }
+        };
+
+        // Inserting at the index of the neighbour puts the todo after it if it came before, as
+        // the removal shifted the neighbour back, and before it otherwise
+        let label = self.describe("move", id) + if up { " up" } else { " down" };
+        self.record(label, |todos| match parent.is_empty() {
+            true => {
+                let todo = todos.todos.remove(idx);
+                todos.write(StoreChange::Deleted(idx));
+                todos.todos.insert(neighbour, todo);
+                todos.write(StoreChange::Added(neighbour));
+            }
+            false => {
+                if let Some(parent_todo) = get_in_mut(&mut todos.todos, parent) {
+                    let todo = parent_todo.children.remove(idx);
+                    parent_todo.children.insert(neighbour, todo);
+                }
+                if let Some(change) = edited(parent) {
+                    todos.write(change);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Every tag used by the todos, sorted alphabetically
+    pub fn get_all_tags(&mut self) -> Vec<String> {
+        let mut todos = Vec::new();
+        flatten(&self.todos, &mut todos);
+        let mut tags = Vec::from_iter(todos.iter().flat_map(|todo| todo.tags.iter()).cloned());
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    pub fn get_tag_filter(&mut self) -> Option<&String> {
+        self.tag_filter.as_ref()
+    }
+
+    /// Filters the todos on the next tag in alphabetical order, the last one removing the filter
+    pub fn cycle_tag_filter(&mut self) {
+        let tags = self.get_all_tags();
+        self.tag_filter = match &self.tag_filter {
+            None => tags.first().cloned(),
+            Some(current) => tags.iter().find(|tag| *tag > current).cloned(),
+        };
+        self.refresh_view();
+    }
+
+    pub fn clear_tag_filter(&mut self) {
+        self.tag_filter = None;
+        self.refresh_view();
+    }
+
+    pub fn get_time_filter(&mut self) -> Option<TimeFilter> {
+        self.time_filter
+    }
+
+    /// Filters the todos on the next preset of `TIME_FILTERS`, the last one removing the filter
+    pub fn cycle_time_filter(&mut self) {
+        self.time_filter = match self.time_filter {
+            None => TIME_FILTERS.first().copied(),
+            Some(current) => TIME_FILTERS
+                .iter()
+                .skip_while(|filter| **filter != current)
+                .nth(1)
+                .copied(),
+        };
+        self.refresh_view();
+    }
+
+    pub fn clear_time_filter(&mut self) {
+        self.time_filter = None;
+        self.refresh_view();
+    }
+
+    fn matches_search(&self, todo: &Todo) -> bool {
+        self.search
+            .as_ref()
+            .is_none_or(|query| search::matches(todo, query))
+    }
+
+    pub fn get_search(&mut self) -> Option<&String> {
+        self.search.as_ref()
+    }
+
+    /// Filters the todos on what is being searched, `None` listing them all again
+    pub fn set_search(&mut self, query: Option<String>) {
+        self.search = query;
+        self.refresh_view();
+    }
+
+    /// Every todo matching the query of the fuzzy finder, whatever its list and the filters,
+    /// the best matches first
+    pub fn fuzzy_find(&mut self, query: &str) -> Vec<FuzzyHit> {
+        let mut todos = Vec::new();
+        flatten(&self.todos, &mut todos);
+        search::fuzzy_rank(&todos, query)
+    }
+
+    /// Makes sure the todo is listed, by showing its list, expanding its ancestors and removing
+    /// the filters hiding it
+    pub fn reveal(&mut self, id: Uuid) {
+        let path = match find_path(&self.todos, id) {
+            Some(path) => path,
+            None => return,
+        };
+        self.list = self.todos[path[0]].list.clone();
+        let mut expanded = false;
+        for depth in 1..path.len() {
+            if let Some(ancestor) = get_in_mut(&mut self.todos, &path[..depth]) {
+                expanded |= ancestor.collapsed;
+                ancestor.collapsed = false;
             }
         }
+        match edited(&path) {
+            Some(change) if expanded => self.write(change),
+            _ => self.refresh_view(),
+        }
+        if !self.view.contains(&path) {
+            self.tag_filter = None;
+            self.time_filter = None;
+            self.search = None;
+            self.refresh_view();
+        }
     }
 
-    pub fn get_todo(&mut self, idx: usize) -> Option<&Todo> {
-        self.todos.get(idx)
+    /// Rows of the todos matching the search, leaving out the parents only listed for their
+    /// matching subtasks
+    pub fn get_search_hits(&mut self) -> Vec<usize> {
+        match &self.search {
+            Some(_) => Vec::from_iter(self.view.iter().enumerate().filter_map(|(row, path)| {
+                get_in(&self.todos, path)
+                    .filter(|todo| self.matches_search(todo))
+                    .map(|_| row)
+            })),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn get_sort(&mut self) -> Sort {
+        self.sort
+    }
+
+    pub fn set_sort(&mut self, sort: Sort) {
+        self.sort = sort;
+        self.refresh_view();
+    }
+
+    /// Sorts the todos by the next key, in the direction that makes sense for it
+    pub fn cycle_sort_key(&mut self) {
+        let key = self.sort.key.next();
+        self.set_sort(Sort {
+            key,
+            descending: key.descending_by_default(),
+            ..self.sort
+        });
+    }
+
+    pub fn reverse_sort(&mut self) {
+        self.set_sort(Sort {
+            descending: !self.sort.descending,
+            ..self.sort
+        });
+    }
+
+    pub fn toggle_closed_last(&mut self) {
+        self.set_sort(Sort {
+            closed_last: !self.sort.closed_last,
+            ..self.sort
+        });
+    }
+
+    /// Persists a change, the view being refreshed first as every change goes through here
+    fn write(&mut self, change: StoreChange) {
+        self.refresh_view();
+        if self.is_read_only() {
+            return;
+        }
+        self.track(&change);
+        if let Some(pending) = &mut self.pending {
+            pending.push(change);
+            return;
+        }
+        let written = self.store.apply(change, &self.todos);
+        self.check_backup();
+        if let Err(error) = written {
+            self.error = Some(self.write_error(error));
+        }
+    }
+
+    fn write_error(&self, error: io::Error) -> String {
+        format!(
+            "couldn't write the todos to '{}': {error}",
+            self.store.location()
+        )
+    }
+
+    /// Runs `action` with the changes it makes to the todos written at once, so that they are
+    /// stored whole or not at all. The todos go back to how they were if the write fails
+    fn batch<R>(&mut self, action: impl FnOnce(&mut Self) -> R) -> (R, Result<(), String>) {
+        if self.pending.is_some() {
+            return (action(self), Ok(()));
+        }
+        let before = (self.todos.clone(), self.stored.clone());
+        self.pending = Some(Vec::new());
+        let result = action(self);
+        let changes = self.pending.take().unwrap_or_default();
+        let written = match self.aborted.take() {
+            Some(error) => Err(error),
+            None if changes.is_empty() => Ok(()),
+            None => self
+                .store
+                .apply_all(&changes, &self.todos)
+                .map_err(|error| self.write_error(error)),
+        };
+        self.check_backup();
+        if written.is_err() {
+            (self.todos, self.stored) = before;
+            self.refresh_view();
+        }
+        (result, written)
+    }
+
+    /// Puts the trash and the archive back as they were before the steps, as they are stored
+    /// apart from the todos and aren't rolled back along with them
+    fn revert_stored_apart(&mut self, steps: &[Step]) {
+        for step in steps.iter().rev().filter(|step| step.is_stored_apart()) {
+            let _ = self.apply_step(step.clone().inverse());
+        }
+    }
+
+    /// Keeps the error of the backup made by the last write, if it failed
+    fn check_backup(&mut self) {
+        if let Some(error) = self.store.take_backup_error() {
+            self.error = Some(format!(
+                "couldn't back up '{}': {error}",
+                self.store.location()
+            ));
+        }
+    }
+
+    /// Takes the error of the last write that failed, to show it
+    pub fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    /// Adds the change to the command being recorded, if any, the copy of the stored todos
+    /// telling what it replaced
+    fn track(&mut self, change: &StoreChange) {
+        let step = match *change {
+            StoreChange::Added(idx) if idx <= self.stored.len() => match self.todos.get(idx) {
+                Some(todo) => {
+                    self.stored.insert(idx, todo.clone());
+                    Step::Added {
+                        idx,
+                        todo: todo.clone(),
+                    }
+                }
+                None => return,
+            },
+            StoreChange::Edited(idx) if idx < self.stored.len() => match self.todos.get(idx) {
+                Some(todo) => Step::Edited {
+                    idx,
+                    before: Box::new(std::mem::replace(&mut self.stored[idx], todo.clone())),
+                    after: Box::new(todo.clone()),
+                },
+                None => return,
+            },
+            StoreChange::Deleted(idx) if idx < self.stored.len() => Step::Deleted {
+                idx,
+                todo: self.stored.remove(idx),
+            },
+            _ => return,
+        };
+        if let Some(command) = &mut self.recording {
+            command.steps.push(step);
+        }
+    }
+
+    /// Runs an action of the user as a single command, undone and redone at once. Nothing of it
+    /// is kept if the todos can't be written
+    fn record<R>(&mut self, label: String, action: impl FnOnce(&mut Self) -> R) -> R {
+        self.recording = Some(Command::new(label));
+        let (result, written) = self.batch(action);
+        let command = self.recording.take();
+        match written {
+            Ok(()) => {
+                if let Some(command) = command {
+                    self.history.push(command);
+                }
+            }
+            Err(error) => {
+                if let Some(command) = command {
+                    self.revert_stored_apart(&command.steps);
+                }
+                self.error = Some(error);
+            }
+        }
+        result
+    }
+
+    /// Describes an action on the todo for the history, such as "delete 'milk'"
+    fn describe(&self, action: &str, id: Uuid) -> String {
+        match find_by_id(&self.todos, id) {
+            Some(todo) => format!("{action} '{}'", todo.title_or_placeholder()),
+            None => String::from(action),
+        }
+    }
+
+    /// Applies the step, the changes to the todos being written by the command being run. The
+    /// trash and the archive are written right away, and left as they were if that fails
+    fn apply_step(&mut self, step: Step) -> Result<(), String> {
+        match step {
+            Step::Added { idx, todo } if idx <= self.todos.len() => {
+                self.todos.insert(idx, todo);
+                self.write(StoreChange::Added(idx));
+            }
+            Step::Edited { idx, after, .. } if idx < self.todos.len() => {
+                self.todos[idx] = *after;
+                self.write(StoreChange::Edited(idx));
+            }
+            Step::Deleted { idx, .. } if idx < self.todos.len() => {
+                self.todos.remove(idx);
+                self.write(StoreChange::Deleted(idx));
+            }
+            // The trash is purged outside of the history, so its entries are found by id
+            Step::Trashed { idx, entry } => return self.trash.insert(idx, *entry),
+            Step::Untrashed { entry, .. } => return self.trash.remove(entry.todo.id).map(|_| ()),
+            Step::Archived { idx, todo } => return self.archive.insert(idx, *todo),
+            Step::Unarchived { todo, .. } => return self.archive.remove(todo.id).map(|_| ()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Applies a change to the trash or to the archive, recording it in the command being run.
+    /// When it fails, the command is aborted and false is returned
+    fn apply_and_record(&mut self, step: Step) -> bool {
+        if let Err(error) = self.apply_step(step.clone()) {
+            self.aborted = Some(error);
+            return false;
+        }
+        if let Some(command) = &mut self.recording {
+            command.steps.push(step);
+        }
+        true
+    }
+
+    /// Applies the steps with a single write of the todos, none of them being kept if it fails
+    fn apply_steps(&mut self, steps: &[Step]) -> Result<(), String> {
+        let (applied, written) = self.batch(|todos| {
+            for (idx, step) in steps.iter().enumerate() {
+                if let Err(error) = todos.apply_step(step.clone()) {
+                    todos.aborted = Some(error);
+                    return idx;
+                }
+            }
+            steps.len()
+        });
+        if written.is_err() {
+            self.revert_stored_apart(&steps[..applied]);
+        }
+        written
+    }
+
+    /// Reverts the last command, returning what it did
+    pub fn undo(&mut self) -> Result<Option<String>, String> {
+        let command = match self.history.pop_undo() {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+        let steps = Vec::from_iter(command.steps.iter().rev().cloned().map(Step::inverse));
+        if let Err(error) = self.apply_steps(&steps) {
+            self.history.push_undo(command);
+            return Err(error);
+        }
+        let label = command.label.clone();
+        self.history.push_redo(command);
+        Ok(Some(label))
+    }
+
+    /// Runs the last undone command again, returning what it did
+    pub fn redo(&mut self) -> Result<Option<String>, String> {
+        let command = match self.history.pop_redo() {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+        if let Err(error) = self.apply_steps(&command.steps) {
+            self.history.push_redo(command);
+            return Err(error);
+        }
+        let label = command.label.clone();
+        self.history.push_undo(command);
+        Ok(Some(label))
+    }
+
+    /// Brings back the history of the last session, unless the todos changed since
+    pub fn load_history(&mut self, file_path: String) {
+        self.history = History::load(&file_path, &self.todos);
+        self.history_path = Some(file_path);
+    }
+
+    pub fn save_history(&mut self) -> Result<(), String> {
+        match &self.history_path {
+            Some(file_path) => self.history.save(file_path, &self.todos),
+            None => Ok(()),
+        }
+    }
+
+    /// Forgets the purged todos and their subtasks in the history, which is saved right away
+    /// for them not to be kept there
+    fn forget(&mut self, purged: &[TrashEntry]) -> Result<(), String> {
+        let mut todos = Vec::new();
+        for entry in purged {
+            flatten(std::slice::from_ref(&entry.todo), &mut todos);
+        }
+        self.history
+            .forget(&Vec::from_iter(todos.iter().map(|todo| todo.id)));
+        self.save_history()
+            .map_err(|error| format!("couldn't save the history: {error}"))
+    }
+
+    /// Applies a change to the todo and persists it, returning false if there is no such todo
+    fn modify(&mut self, id: Uuid, change: impl FnOnce(&mut Todo)) -> bool {
+        let path = match find_path(&self.todos, id) {
+            Some(path) => path,
+            None => return false,
+        };
+        if let Some(todo) = get_in_mut(&mut self.todos, &path) {
+            change(todo);
+        }
+        if let Some(change) = edited(&path) {
+            self.write(change);
+        }
+        true
+    }
+
+    pub fn keeps_backups(&mut self) -> bool {
+        self.store.keeps_backups()
+    }
+
+    pub fn get_backups(&mut self) -> Vec<PathBuf> {
+        self.store.backups()
+    }
+
+    pub fn restore_backup(&mut self, backup: &Path) -> Result<(), String> {
+        let restored = self.store.restore(backup);
+        self.check_backup();
+        let todos = restored.map_err(|error| {
+            format!(
+                "couldn't restore the backup '{}': {error}",
+                backup.display()
+            )
+        })?;
+        self.set_todos(todos);
+        self.load_error = None;
+        Ok(())
+    }
+
+    pub fn get_location(&mut self) -> String {
+        self.store.location()
+    }
+
+    pub fn is_read_only(&mut self) -> bool {
+        self.load_error.is_some() || self.read_only_reason.is_some()
+    }
+
+    pub fn get_read_only_reason(&mut self) -> Option<&String> {
+        self.read_only_reason.as_ref()
+    }
+
+    pub fn get_load_error(&mut self) -> Option<&CorruptData> {
+        self.load_error.as_ref()
+    }
+
+    pub fn prepare_salvage(&mut self) {
+        self.salvaged = Some(self.store.salvage());
+    }
+
+    pub fn get_salvaged_titles(&mut self) -> Option<Vec<String>> {
+        self.salvaged
+            .as_ref()
+            .map(|todos| Vec::from_iter(todos.iter().map(|t| t.title.clone())))
+    }
+
+    pub fn cancel_salvage(&mut self) {
+        self.salvaged = None;
+    }
+
+    /// Replaces the corrupt data with the salvaged todos
+    pub fn confirm_salvage(&mut self) -> Result<(), String> {
+        let Some(todos) = self.salvaged.take() else {
+            return Ok(());
+        };
+        let location = self.store.location();
+        let saved = self.store.save(&todos);
+        self.check_backup();
+        saved.map_err(|error| format!("couldn't write the todos to '{location}': {error}"))?;
+        self.set_todos(todos);
+        self.load_error = None;
+        Ok(())
+    }
+
+    pub fn get_todo(&mut self, id: Uuid) -> Option<&Todo> {
+        find_by_id(&self.todos, id)
     }
 
     pub fn get_nb_todos(&mut self) -> usize {
         self.todos.len()
     }
 
-    pub fn add(&mut self, title: String, description: String, due_date: String, done: bool) {
-        self.todos.push(Todo {
-            title,
-            description,
+    /// Adds the todo being written in the create screen, as a subtask of `parent` if given
+    pub fn add(
+        &mut self,
+        parent: Option<Uuid>,
+        states: &mut States,
+        due_date: Option<DueDate>,
+        recurrence: Option<Recurrence>,
+    ) {
+        let now = Local::now();
+        let recurrence = recurrence.map(|recurrence| recurrence.pinned(due_date, now.date_naive()));
+        let todo = Todo {
+            title: states.get_title().to_owned(),
+            description: states.get_description().to_owned(),
             due_date,
-            done,
+            status: String::from(self.statuses.initial()),
+            priority: states.get_priority(),
+            tags: states.get_tags(),
+            children: Vec::new(),
+            collapsed: false,
+            blocked_by: Vec::new(),
+            id: Uuid::new_v4(),
+            recurrence,
+            completions: Vec::new(),
+            created_at: Some(now),
+            updated_at: Some(now),
+            completed_at: None,
+            list: self.list.clone(),
+        };
+        let label = format!("add '{}'", todo.title_or_placeholder());
+        self.record(label, |todos| match parent {
+            None => {
+                todos.todos.push(todo);
+                todos.write(StoreChange::Added(todos.todos.len() - 1));
+            }
+            Some(parent) => {
+                todos.modify(parent, |parent| {
+                    parent.children.push(todo);
+                    parent.collapsed = false;
+                    parent.updated_at = Some(now);
+                });
+            }
         });
-        self.write();
     }
 
-    pub fn edit(&mut self, idx: usize, states: &mut States) {
-        if let Some(todo) = self.todos.get_mut(idx) {
-            todo.title = String::from(states.get_title());
-            todo.description = String::from(states.get_description());
-            todo.due_date = String::from(states.get_date());
-            self.write();
+    pub fn edit(
+        &mut self,
+        id: Uuid,
+        states: &mut States,
+        due_date: Option<DueDate>,
+        recurrence: Option<Recurrence>,
+    ) {
+        let label = self.describe("edit", id);
+        let now = Local::now();
+        let recurrence = recurrence.map(|recurrence| recurrence.pinned(due_date, now.date_naive()));
+        self.record(label, |todos| {
+            todos.modify(id, |todo| {
+                todo.updated_at = Some(now);
+                todo.recurrence = recurrence;
+                todo.title = String::from(states.get_title());
+                todo.description = String::from(states.get_description());
+                todo.due_date = due_date;
+                todo.priority = states.get_priority();
+                todo.tags = states.get_tags();
+            })
+        });
+    }
+
+    /// Moves the todo to the trash along with its subtasks. The trash is written first, so that
+    /// the todo is never lost from both
+    pub fn delete(&mut self, id: Uuid) {
+        let entry = match self.trash_entry(id) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let label = self.describe("delete", id);
+        self.record(label, |todos| {
+            let trashed = todos.apply_and_record(Step::Trashed {
+                idx: todos.trash.entries().len(),
+                entry: Box::new(entry),
+            });
+            if trashed {
+                todos.remove(id);
+            }
+        });
+    }
+
+    /// What the trash keeps of the todo, along with where it was
+    fn trash_entry(&self, id: Uuid) -> Option<TrashEntry> {
+        let path = find_path(&self.todos, id)?;
+        let (_, parent) = path.split_last()?;
+        Some(TrashEntry {
+            todo: get_in(&self.todos, &path)?.clone(),
+            deleted_at: Local::now(),
+            parent: get_in(&self.todos, parent).map(|parent| parent.id),
+            list: self.todos.get(path[0])?.list.clone(),
+        })
+    }
+
+    /// Takes the todo out of the tree
+    fn remove(&mut self, id: Uuid) {
+        let path = match find_path(&self.todos, id) {
+            Some(path) => path,
+            None => return,
+        };
+        match path.split_last() {
+            Some((&idx, [])) if idx < self.todos.len() => {
+                self.todos.remove(idx);
+                self.write(StoreChange::Deleted(idx));
+            }
+            Some((&idx, parent)) if !parent.is_empty() => {
+                match get_in_mut(&mut self.todos, parent) {
+                    Some(parent_todo) if idx < parent_todo.children.len() => {
+                        parent_todo.children.remove(idx);
+                        parent_todo.updated_at = Some(Local::now());
+                    }
+                    _ => return,
+                }
+                if let Some(change) = edited(parent) {
+                    self.write(change);
+                }
+            }
+            _ => {}
         }
     }
 
-    pub fn delete(&mut self, idx: usize) {
-        self.todos.remove(idx);
-        self.write();
+    pub fn get_trash(&mut self) -> &[TrashEntry] {
+        self.trash.entries()
+    }
+
+    pub fn set_trash(&mut self, trash: Trash) {
+        self.trash = trash;
+    }
+
+    /// Puts the todo back under its parent, or at the end of its list if the parent is gone
+    pub fn restore_from_trash(&mut self, id: Uuid) {
+        let (idx, entry) = match (self.trash.position(id), self.trash.get(id)) {
+            (Some(idx), Some(entry)) => (idx, entry.clone()),
+            _ => return,
+        };
+        let label = format!("restore '{}'", entry.todo.title_or_placeholder());
+        self.record(label, |todos| {
+            todos.apply_and_record(Step::Untrashed {
+                idx,
+                entry: Box::new(entry.clone()),
+            });
+            let TrashEntry {
+                mut todo,
+                parent,
+                list,
+                ..
+            } = entry;
+            let now = Local::now();
+            todo.updated_at = Some(now);
+            let restored = parent.is_some_and(|parent| {
+                todos.modify(parent, |parent| {
+                    parent.children.push(todo.clone());
+                    parent.collapsed = false;
+                    parent.updated_at = Some(now);
+                })
+            });
+            if !restored {
+                todo.list = list;
+                todos.todos.push(todo);
+                todos.write(StoreChange::Added(todos.todos.len() - 1));
+            }
+        });
     }
 
-    pub fn get_todos_titles(&mut self) -> Vec<String> {
-        Vec::from_iter(self.todos.iter().map(|t| match t.done {
-            true => format!(
-                "✔ {}",
-                if t.title.is_empty() {
-                    String::from("N/A")
-                } else {
-                    t.title.clone()
+    /// Deletes the todo from the trash for good, the history forgetting it too
+    pub fn purge(&mut self, id: Uuid) {
+        let purged = match self.trash.remove(id) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        };
+        if let Err(error) = self.forget(&[purged]) {
+            self.error = Some(error);
+        }
+    }
+
+    pub fn get_archive(&mut self) -> &[Todo] {
+        self.archive.todos()
+    }
+
+    pub fn set_archive(&mut self, archive: Archive) {
+        self.archive = archive;
+    }
+
+    /// Archived todos matching the query, the most recently archived first
+    pub fn search_archive(&mut self, query: &str) -> Vec<&Todo> {
+        Vec::from_iter(
+            self.archive
+                .todos()
+                .iter()
+                .rev()
+                .filter(|todo| search::matches(todo, query)),
+        )
+    }
+
+    /// Top-level todos that are closed along with all of their subtasks, and that were completed
+    /// at least `days` ago when given. Those completed before it was recorded count as old
+    pub fn get_archivable(&mut self, days: Option<u64>) -> Vec<Uuid> {
+        let limit = days.and_then(|days| Local::now().checked_sub_days(Days::new(days)));
+        Vec::from_iter(
+            self.todos
+                .iter()
+                .filter(|todo| {
+                    let (closed, total) = todo.progress(&self.statuses);
+                    self.statuses.is_closed(&todo.status)
+                        && closed == total
+                        && limit.is_none_or(|limit| {
+                            todo.completed_at
+                                .is_none_or(|completed_at| completed_at <= limit)
+                        })
+                })
+                .map(|todo| todo.id),
+        )
+    }
+
+    /// Moves the todos that can be archived to the archive, returning how many were. A todo is
+    /// only taken out of its list once it is in the archive, none of them being moved if the
+    /// archive or the todos can't be written
+    pub fn archive_closed(&mut self, days: Option<u64>) -> usize {
+        if let Some(reason) = self.archive.get_read_only_reason() {
+            self.error = Some(reason.clone());
+            return 0;
+        }
+        let ids = self.get_archivable(days);
+        let label = match ids.as_slice() {
+            [id] => self.describe("archive", *id),
+            _ => format!("archive {} todos", ids.len()),
+        };
+        self.record(label, |todos| {
+            for &id in &ids {
+                if let Some(idx) = todos.todos.iter().position(|todo| todo.id == id) {
+                    let archived = todos.apply_and_record(Step::Archived {
+                        idx: todos.archive.todos().len(),
+                        todo: Box::new(todos.todos[idx].clone()),
+                    });
+                    if !archived {
+                        return;
+                    }
+                    todos.todos.remove(idx);
+                    todos.write(StoreChange::Deleted(idx));
                 }
-            ),
-            false => format!(
-                "✘ {}",
-                if t.title.is_empty() {
-                    String::from("N/A")
-                } else {
-                    t.title.clone()
+            }
+        });
+        // The todos are put back in their lists when they couldn't all be archived
+        ids.iter()
+            .filter(|&&id| self.archive.position(id).is_some())
+            .count()
+    }
+
+    /// Puts the archived todo back at the end of its list
+    pub fn unarchive(&mut self, id: Uuid) {
+        if let Some(reason) = self.archive.get_read_only_reason() {
+            self.error = Some(reason.clone());
+            return;
+        }
+        let (idx, todo) = match (self.archive.position(id), self.archive.get(id)) {
+            (Some(idx), Some(todo)) => (idx, todo.clone()),
+            _ => return,
+        };
+        let label = format!("unarchive '{}'", todo.title_or_placeholder());
+        self.record(label, |todos| {
+            let unarchived = todos.apply_and_record(Step::Unarchived {
+                idx,
+                todo: Box::new(todo.clone()),
+            });
+            if !unarchived {
+                return;
+            }
+            todos.todos.push(todo);
+            todos.write(StoreChange::Added(todos.todos.len() - 1));
+        });
+    }
+
+    /// Purges the todos deleted more than `days` ago, 0 keeping them forever
+    pub fn purge_trash_older_than(&mut self, days: u64) -> Result<(), String> {
+        let purged = self.trash.purge_older_than(days, Local::now())?;
+        match purged.is_empty() {
+            true => Ok(()),
+            false => self.forget(&purged),
+        }
+    }
+
+    pub fn get_todos_titles(&mut self) -> Vec<Line<'static>> {
+        Vec::from_iter(self.view.iter().filter_map(|path| {
+            let t = get_in(&self.todos, path)?;
+            let mut spans = vec![Span::raw("  ".repeat(path.len() - 1))];
+            spans.push(Span::raw(match (t.children.is_empty(), t.collapsed) {
+                (true, _) => "  ",
+                (false, true) => "▸ ",
+                (false, false) => "▾ ",
+            }));
+            let closed = self.statuses.is_closed(&t.status);
+            let blocked = !closed && !open_blockers(&self.todos, &self.statuses, t).is_empty();
+            spans.push(match blocked {
+                true => Span::raw("⛓ "),
+                false => {
+                    let glyph = Span::raw(format!("{} ", self.statuses.glyph(&t.status)));
+                    match self.statuses.color(&t.status) {
+                        Some(color) => glyph.fg(color),
+                        None => glyph,
+                    }
                 }
-            ),
+            });
+            if let Some(marker) = t.priority.marker() {
+                spans.push(Span::styled(
+                    format!("{marker} "),
+                    Style::new().fg(t.priority.color()).bold(),
+                ));
+            }
+            let title_style = match blocked {
+                true => Style::new().fg(Color::DarkGray),
+                false => Style::new(),
+            };
+            match &self.search {
+                Some(query) => spans.extend(search::highlight(
+                    &t.title_or_placeholder(),
+                    query,
+                    title_style,
+                )),
+                None => spans.push(Span::styled(t.title_or_placeholder(), title_style)),
+            }
+            if t.recurrence.is_some() {
+                spans.push(Span::raw(" ⟳"));
+            }
+            if !t.children.is_empty() {
+                let (closed, total) = t.progress(&self.statuses);
+                spans.push(Span::raw(format!(" {closed}/{total}")).italic());
+            }
+            if let Some(status) = t.due_status(&self.statuses) {
+                if let (Some(label), Some(color)) = (status.label(), status.color()) {
+                    spans.push(Span::styled(
+                        format!(" ({label})"),
+                        Style::new().fg(color).bold(),
+                    ));
+                }
+            }
+            for tag in &t.tags {
+                spans.push(Span::raw(" "));
+                spans.push(tag_chip(tag));
+            }
+            Some(match (blocked, self.statuses.kind(&t.status)) {
+                (true, _) => Line::from(spans).dim(),
+                (false, StatusKind::Cancelled) => Line::from(spans).dim().crossed_out(),
+                (false, _) => Line::from(spans),
+            })
         }))
     }
 
-    pub fn get_description(&mut self, idx: usize) -> String {
-        match self.todos.get(idx) {
+    /// The open blockers of the todo, each followed by its own open blockers indented below it
+    pub fn get_blocking_chain(&mut self, id: Uuid) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        if let Some(todo) = find_by_id(&self.todos, id) {
+            let mut visited = HashSet::from([todo.id]);
+            self.push_blockers(todo, 0, &mut visited, &mut lines);
+        }
+        lines
+    }
+
+    fn push_blockers(
+        &self,
+        todo: &Todo,
+        depth: usize,
+        visited: &mut HashSet<Uuid>,
+        lines: &mut Vec<Line<'static>>,
+    ) {
+        for blocker in open_blockers(&self.todos, &self.statuses, todo) {
+            lines.push(Line::from(format!(
+                "{}⛓ {}",
+                "  ".repeat(depth),
+                blocker.title_or_placeholder()
+            )));
+            // Cycles are rejected when linking, but the file could have been edited by hand
+            if visited.insert(blocker.id) {
+                self.push_blockers(blocker, depth + 1, visited, lines);
+            }
+        }
+    }
+
+    /// Links the todo to a blocker it has to wait on, or unlinks them if they already are.
+    /// Returns whether they are now linked
+    pub fn toggle_blocker(&mut self, id: Uuid, blocker: Uuid) -> Result<bool, String> {
+        let (todo, blocker) = match (
+            find_by_id(&self.todos, id),
+            find_by_id(&self.todos, blocker),
+        ) {
+            (Some(todo), Some(blocker)) => (todo, blocker),
+            _ => return Err(String::from("the todo doesn't exist anymore")),
+        };
+        let (todo_id, blocker_id) = (todo.id, blocker.id);
+        let linked = !todo.blocked_by.contains(&blocker_id);
+        if linked && waits_on(&self.todos, blocker_id, todo_id, &mut HashSet::new()) {
+            return Err(match todo_id == blocker_id {
+                true => String::from("a todo can't block itself"),
+                false => format!(
+                    "'{}' already waits on '{}', linking them would make a cycle",
+                    blocker.title_or_placeholder(),
+                    todo.title_or_placeholder()
+                ),
+            });
+        }
+
+        let label = format!(
+            "{} '{}' and '{}'",
+            if linked { "link" } else { "unlink" },
+            todo.title_or_placeholder(),
+            blocker.title_or_placeholder()
+        );
+        self.record(label, |todos| {
+            todos.modify(todo_id, |todo| {
+                match linked {
+                    true => todo.blocked_by.push(blocker_id),
+                    false => todo.blocked_by.retain(|&id| id != blocker_id),
+                }
+                todo.updated_at = Some(Local::now());
+            })
+        });
+        Ok(linked)
+    }
+
+    /// Error explaining why the todos can't be done yet, when one of them has open blockers that
+    /// aren't part of them
+    fn check_not_blocked(&self, todos: &[&Todo]) -> Result<(), String> {
+        let ids = HashSet::<Uuid>::from_iter(todos.iter().map(|todo| todo.id));
+        for todo in todos
+            .iter()
+            .filter(|todo| !self.statuses.is_closed(&todo.status))
+        {
+            let blockers = Vec::from_iter(
+                open_blockers(&self.todos, &self.statuses, todo)
+                    .into_iter()
+                    .filter(|blocker| !ids.contains(&blocker.id))
+                    .map(|blocker| format!("'{}'", blocker.title_or_placeholder())),
+            );
+            if !blockers.is_empty() {
+                return Err(format!(
+                    "'{}' is blocked by {}",
+                    todo.title_or_placeholder(),
+                    blockers.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_description(&mut self, id: Uuid) -> String {
+        match find_by_id(&self.todos, id) {
             Some(todo) => match todo.description.is_empty() {
                 true => String::from("N/A"),
-                false => self.todos.get(idx).unwrap().description.clone(),
+                false => todo.description.clone(),
             },
             None => String::from("N/A"),
         }
     }
 
-    pub fn get_due_date(&mut self, idx: usize) -> String {
-        match self.todos.get(idx) {
-            Some(todo) => match todo.due_date.is_empty() {
-                true => String::from("N/A"),
-                false => self.todos.get(idx).unwrap().due_date.clone(),
-            },
+    pub fn get_due_date(&mut self, id: Uuid) -> String {
+        match find_by_id(&self.todos, id).and_then(|todo| todo.due_date) {
+            Some(due_date) => due_date.to_string(),
             None => String::from("N/A"),
         }
     }
 
-    pub fn is_done(&mut self, idx: usize) -> String {
-        match self.todos.get(idx) {
-            Some(todo) => match todo.done {
-                true => String::from("✅"),
-                false => String::from("❌"),
-            },
+    /// When the todo was created, updated and completed, those that are known
+    pub fn get_timestamps(&mut self, id: Uuid) -> Vec<(TimeField, DateTime<Local>)> {
+        let fields = [TimeField::Created, TimeField::Updated, TimeField::Completed];
+        match find_by_id(&self.todos, id) {
+            Some(todo) => Vec::from_iter(
+                fields
+                    .into_iter()
+                    .filter_map(|field| todo.get_time(field).map(|time| (field, time))),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn get_recurrence(&mut self, id: Uuid) -> Option<Recurrence> {
+        find_by_id(&self.todos, id).and_then(|todo| todo.recurrence.clone())
+    }
+
+    pub fn get_priority(&mut self, id: Uuid) -> Priority {
+        find_by_id(&self.todos, id)
+            .map(|todo| todo.priority)
+            .unwrap_or_default()
+    }
+
+    pub fn get_tags(&mut self, id: Uuid) -> Vec<String> {
+        find_by_id(&self.todos, id)
+            .map(|todo| todo.tags.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn get_due_status(&mut self, id: Uuid) -> Option<DueStatus> {
+        find_by_id(&self.todos, id).and_then(|todo| todo.due_status(&self.statuses))
+    }
+
+    /// Days on which todos that are still open are due
+    pub fn get_due_days(&mut self) -> HashSet<NaiveDate> {
+        let mut todos = Vec::new();
+        flatten(&self.todos, &mut todos);
+        HashSet::from_iter(
+            todos
+                .iter()
+                .filter(|todo| !self.statuses.is_closed(&todo.status))
+                .filter_map(|todo| todo.due_date.map(|due_date| due_date.date)),
+        )
+    }
+
+    pub fn get_status(&mut self, id: Uuid) -> String {
+        match find_by_id(&self.todos, id) {
+            Some(todo) => {
+                let mut status = format!("{} {}", self.statuses.glyph(&todo.status), todo.status);
+                if !self.statuses.is_closed(&todo.status) && !todo.children.is_empty() {
+                    let (closed, total) = todo.progress(&self.statuses);
+                    status.push_str(&format!(" {closed}/{total}"));
+                }
+                if !todo.completions.is_empty() {
+                    status.push_str(&format!(" ({}× done)", todo.completions.len()));
+                }
+                status
+            }
             None => String::from("N/A"),
         }
     }
 
-    pub fn toggle(&mut self, idx: usize) {
-        if let Some(todo) = self.todos.get_mut(idx) {
-            todo.done = !todo.done;
-            self.write();
+    /// Whether cycling the status of the todo would mark it as done
+    pub fn completes_on_cycle(&mut self, id: Uuid) -> bool {
+        find_by_id(&self.todos, id).is_some_and(|todo| {
+            self.statuses.kind(self.statuses.next(&todo.status)) == StatusKind::Done
+        })
+    }
+
+    /// Moves the todo on to the next status, a todo with open blockers not being allowed to be
+    /// done
+    pub fn cycle_status(&mut self, id: Uuid) -> Result<(), String> {
+        let next = match find_by_id(&self.todos, id) {
+            Some(todo) => String::from(self.statuses.next(&todo.status)),
+            None => return Ok(()),
+        };
+        let statuses = self.statuses.clone();
+        let label = self.describe("set", id) + &format!(" to {next}");
+        match statuses.kind(&next) {
+            StatusKind::Done => {
+                if let Some(todo) = find_by_id(&self.todos, id) {
+                    self.check_not_blocked(&[todo])?;
+                }
+                self.record(label, |todos| {
+                    todos.modify(id, |todo| todo.complete(Local::now(), &next, &statuses))
+                });
+            }
+            _ => {
+                self.record(label, |todos| {
+                    todos.modify(id, |todo| {
+                        todo.status = next;
+                        todo.completed_at = None;
+                        todo.updated_at = Some(Local::now());
+                    })
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn count_open_descendants(&mut self, id: Uuid) -> usize {
+        find_by_id(&self.todos, id).map_or(0, |todo| {
+            let (closed, total) = todo.progress(&self.statuses);
+            total - closed
+        })
+    }
+
+    /// Marks the todo and all of its descendants as done, unless one of them waits on a todo
+    /// outside of them
+    pub fn complete_with_children(&mut self, id: Uuid) -> Result<(), String> {
+        if let Some(todo) = find_by_id(&self.todos, id) {
+            let mut subtree = vec![todo];
+            flatten(&todo.children, &mut subtree);
+            self.check_not_blocked(&subtree)?;
+        }
+        let statuses = self.statuses.clone();
+        let label = self.describe("complete", id) + " with its subtasks";
+        self.record(label, |todos| {
+            todos.modify(id, |todo| {
+                todo.complete_recursively(Local::now(), &statuses)
+            })
+        });
+        Ok(())
+    }
+
+    /// Shows or hides the children of the todo, returning false if it has none or was already
+    /// in that state
+    pub fn set_collapsed(&mut self, id: Uuid, collapsed: bool) -> bool {
+        match find_by_id(&self.todos, id) {
+            Some(todo) if !todo.children.is_empty() && todo.collapsed != collapsed => {
+                self.modify(id, |todo| todo.collapsed = collapsed)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    fn new_todos() -> (Todos, MemoryStore) {
+        let store = MemoryStore::default();
+        let todos = match Todos::new(Box::new(store.clone()), Statuses::default()) {
+            Ok(todos) => todos,
+            Err(error) => panic!("couldn't load the todos: {error}"),
+        };
+        (todos, store)
+    }
+
+    /// Adds a todo as the create screen would, returning its id
+    fn add(todos: &mut Todos, parent: Option<Uuid>, title: &str) -> Uuid {
+        let mut states = States::new();
+        if let Some(input) = states.get_selected_input() {
+            input.set(title);
         }
+        todos.add(parent, &mut states, None, None);
+        let siblings = match parent {
+            Some(parent) => &find_by_id(&todos.todos, parent).unwrap().children,
+            None => &todos.todos,
+        };
+        siblings.last().unwrap().id
+    }
+
+    fn status(todos: &mut Todos, id: Uuid) -> String {
+        todos.get_todo(id).unwrap().status.clone()
+    }
+
+    /// Titles of the top-level todos in memory, which may differ from the stored ones
+    fn titles(todos: &Todos) -> Vec<&str> {
+        Vec::from_iter(todos.todos.iter().map(|todo| todo.title.as_str()))
+    }
+
+    #[test]
+    fn add_stores_the_todo() {
+        let (mut todos, store) = new_todos();
+        let id = add(&mut todos, None, "milk");
+        assert_eq!(store.titles(), ["milk"]);
+        let todo = todos.get_todo(id).unwrap();
+        assert_eq!(todo.status, "todo");
+        assert_eq!(todo.list, DEFAULT_LIST);
+        assert!(todo.created_at.is_some());
+    }
+
+    #[test]
+    fn add_subtask_stores_it_in_its_parent() {
+        let (mut todos, store) = new_todos();
+        let parent = add(&mut todos, None, "groceries");
+        let child = add(&mut todos, Some(parent), "milk");
+        assert_eq!(todos.get_parent(child), Some(parent));
+        assert_eq!(store.todos.borrow()[0].children[0].title, "milk");
+    }
+
+    #[test]
+    fn adding_or_deleting_a_subtask_updates_its_parent() {
+        let (mut todos, store) = new_todos();
+        let parent = add(&mut todos, None, "groceries");
+        todos.todos[0].updated_at = None;
+        let child = add(&mut todos, Some(parent), "milk");
+        assert!(store.todos.borrow()[0].updated_at.is_some());
+
+        todos.todos[0].updated_at = None;
+        todos.delete(child);
+        assert!(todos.get_todo(parent).unwrap().updated_at.is_some());
+        assert!(store.todos.borrow()[0].updated_at.is_some());
+    }
+
+    #[test]
+    fn edit_replaces_the_fields() {
+        let (mut todos, store) = new_todos();
+        let id = add(&mut todos, None, "milk");
+        let mut states = States::new();
+        if let Some(input) = states.get_selected_input() {
+            input.set("oat milk");
+        }
+        states.raise_priority();
+        todos.edit(id, &mut states, None, None);
+        assert_eq!(store.titles(), ["oat milk"]);
+        assert_ne!(todos.get_priority(id), Priority::None);
+    }
+
+    #[test]
+    fn delete_moves_the_todo_to_the_trash() {
+        let (mut todos, store) = new_todos();
+        let parent = add(&mut todos, None, "groceries");
+        add(&mut todos, Some(parent), "milk");
+        todos.delete(parent);
+        assert!(store.titles().is_empty());
+        assert!(todos.get_todo(parent).is_none());
+        let trash = todos.get_trash();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].todo.children.len(), 1);
+
+        todos.restore_from_trash(parent);
+        assert_eq!(store.titles(), ["groceries"]);
+        assert!(todos.get_trash().is_empty());
+    }
+
+    #[test]
+    fn delete_keeps_the_todo_when_the_trash_cant_be_written() {
+        let (mut todos, store) = new_todos();
+        todos.set_trash(Trash::load(String::from("/nonexistent/todo-tui/trash.json")).unwrap());
+        let id = add(&mut todos, None, "milk");
+        todos.delete(id);
+        assert!(todos.take_error().is_some());
+        assert!(todos.get_todo(id).is_some());
+        assert_eq!(store.titles(), ["milk"]);
+        assert!(todos.get_trash().is_empty());
+    }
+
+    #[test]
+    fn delete_empties_the_trash_again_when_the_todos_cant_be_written() {
+        let trash_path =
+            std::env::temp_dir().join(format!("todo-tui-trash-{}.json", std::process::id()));
+        let trash_path = trash_path.to_string_lossy().into_owned();
+        let (mut todos, store) = new_todos();
+        todos.set_trash(Trash::load(trash_path.clone()).unwrap());
+        let id = add(&mut todos, None, "milk");
+        store.fail.set(true);
+        todos.delete(id);
+        assert!(todos.take_error().is_some());
+        assert!(todos.get_todo(id).is_some());
+        assert!(todos.get_trash().is_empty());
+        assert!(Trash::load(trash_path.clone())
+            .unwrap()
+            .entries()
+            .is_empty());
+        let _ = std::fs::remove_file(trash_path);
+    }
+
+    #[test]
+    fn purge_forgets_the_todo_in_the_history() {
+        let dir = std::env::temp_dir().join(format!("todo-tui-purge-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let trash_path = dir.join("trash.json").to_string_lossy().into_owned();
+        let history_path = dir.join("history.json").to_string_lossy().into_owned();
+        let (mut todos, _) = new_todos();
+        todos.set_trash(Trash::load(trash_path).unwrap());
+        todos.load_history(history_path.clone());
+        add(&mut todos, None, "bread");
+        let id = add(&mut todos, None, "milk");
+        todos.delete(id);
+        todos.purge(id);
+        assert!(todos.take_error().is_none());
+        assert!(todos.get_trash().is_empty());
+        while let Ok(Some(_)) = todos.undo() {}
+        assert!(todos.get_todo(id).is_none());
+        assert!(!std::fs::read_to_string(history_path)
+            .unwrap()
+            .contains(&id.to_string()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn archive_closed_keeps_the_todos_when_the_archive_cant_be_written() {
+        let (mut todos, store) = new_todos();
+        let archive_store = MemoryStore::default();
+        todos.set_archive(Archive::open(Box::new(archive_store.clone())).unwrap());
+        let id = add(&mut todos, None, "milk");
+        assert!(todos.complete_with_children(id).is_ok());
+        archive_store.fail.set(true);
+        assert_eq!(todos.archive_closed(None), 0);
+        assert!(todos.take_error().is_some());
+        assert!(todos.get_todo(id).is_some());
+        assert_eq!(store.titles(), ["milk"]);
+        assert!(todos.get_archive().is_empty());
+
+        archive_store.fail.set(false);
+        assert_eq!(todos.archive_closed(None), 1);
+        assert!(todos.get_todo(id).is_none());
+        assert!(store.titles().is_empty());
+        assert_eq!(archive_store.titles(), ["milk"]);
+    }
+
+    #[test]
+    fn cycle_status_goes_through_the_statuses() {
+        let (mut todos, store) = new_todos();
+        let id = add(&mut todos, None, "milk");
+        assert!(todos.cycle_status(id).is_ok());
+        assert_eq!(status(&mut todos, id), "doing");
+        assert!(todos.cycle_status(id).is_ok());
+        assert_eq!(status(&mut todos, id), "done");
+        assert!(todos.get_todo(id).unwrap().completed_at.is_some());
+        assert_eq!(store.todos.borrow()[0].status, "done");
+        assert!(todos.cycle_status(id).is_ok());
+        assert_eq!(status(&mut todos, id), "cancelled");
+        assert!(todos.get_todo(id).unwrap().completed_at.is_none());
+    }
+
+    #[test]
+    fn cycle_status_refuses_to_complete_a_blocked_todo() {
+        let (mut todos, _) = new_todos();
+        let id = add(&mut todos, None, "bake");
+        let blocker = add(&mut todos, None, "buy flour");
+        assert_eq!(todos.toggle_blocker(id, blocker), Ok(true));
+        assert!(todos.cycle_status(id).is_ok());
+        assert!(todos.cycle_status(id).is_err());
+        assert_eq!(status(&mut todos, id), "doing");
+    }
+
+    #[test]
+    fn move_to_list_is_written_at_once() {
+        let (mut todos, store) = new_todos();
+        let parent = add(&mut todos, None, "groceries");
+        let child = add(&mut todos, Some(parent), "milk");
+        todos.add_list("errands");
+        store.writes.set(0);
+        todos.move_to_list(child);
+        assert_eq!(store.writes.get(), 1);
+        assert_eq!(store.titles(), ["groceries", "milk"]);
+        assert_eq!(store.todos.borrow()[1].list, "errands");
+        assert!(store.todos.borrow()[0].children.is_empty());
+    }
+
+    #[test]
+    fn a_failed_write_leaves_the_todos_unchanged() {
+        let (mut todos, store) = new_todos();
+        let parent = add(&mut todos, None, "groceries");
+        let child = add(&mut todos, Some(parent), "milk");
+        todos.add_list("errands");
+        store.fail.set(true);
+        todos.move_to_list(child);
+        assert!(todos.take_error().is_some());
+        assert_eq!(todos.get_parent(child), Some(parent));
+        assert_eq!(todos.get_nb_todos(), 1);
+        // Nothing to undo, the command wasn't kept
+        store.fail.set(false);
+        assert_eq!(todos.undo().unwrap().as_deref(), Some("add 'milk'"));
+    }
+
+    #[test]
+    fn move_todo_swaps_it_with_its_listed_neighbour() {
+        let (mut todos, store) = new_todos();
+        add(&mut todos, None, "a");
+        add(&mut todos, None, "b");
+        let c = add(&mut todos, None, "c");
+        assert!(todos.move_todo(c, true).is_ok());
+        assert_eq!(store.titles(), ["a", "c", "b"]);
+        assert!(todos.move_todo(c, true).is_ok());
+        assert!(todos.move_todo(c, true).is_ok());
+        assert_eq!(store.titles(), ["c", "a", "b"]);
+        assert!(todos.move_todo(c, false).is_ok());
+        assert_eq!(store.titles(), ["a", "c", "b"]);
+    }
+
+    #[test]
+    fn a_reorder_is_written_at_once() {
+        let (mut todos, store) = new_todos();
+        add(&mut todos, None, "a");
+        let b = add(&mut todos, None, "b");
+        store.writes.set(0);
+        assert!(todos.move_todo(b, true).is_ok());
+        assert_eq!(store.writes.get(), 1);
+        assert_eq!(store.titles(), ["b", "a"]);
+
+        assert!(todos.undo().unwrap().is_some());
+        assert_eq!(store.writes.get(), 2);
+        assert_eq!(store.titles(), ["a", "b"]);
+    }
+
+    #[test]
+    fn a_failed_reorder_or_undo_changes_nothing() {
+        let (mut todos, store) = new_todos();
+        add(&mut todos, None, "a");
+        let b = add(&mut todos, None, "b");
+        store.fail.set(true);
+        assert!(todos.move_todo(b, true).is_ok());
+        assert!(todos.take_error().is_some());
+        assert_eq!(titles(&todos), ["a", "b"]);
+
+        store.fail.set(false);
+        assert!(todos.move_todo(b, true).is_ok());
+        store.fail.set(true);
+        assert!(todos.undo().is_err());
+        assert_eq!(titles(&todos), ["b", "a"]);
+        store.fail.set(false);
+        assert!(todos.undo().unwrap().is_some());
+        assert_eq!(store.titles(), ["a", "b"]);
+    }
+
+    #[test]
+    fn move_todo_needs_the_manual_order() {
+        let (mut todos, store) = new_todos();
+        add(&mut todos, None, "b");
+        let a = add(&mut todos, None, "a");
+        todos.cycle_sort_key();
+        assert!(todos.move_todo(a, true).is_err());
+        assert_eq!(store.titles(), ["b", "a"]);
+    }
+
+    #[test]
+    fn a_failed_write_is_kept_to_be_shown() {
+        let (mut todos, store) = new_todos();
+        store.fail.set(true);
+        todos.add(None, &mut States::new(), None, None);
+        assert!(todos.take_error().is_some());
+        assert!(todos.take_error().is_none());
+        assert!(store.titles().is_empty());
+    }
+
+    #[test]
+    fn undo_and_redo_replay_the_commands() {
+        let (mut todos, store) = new_todos();
+        let id = add(&mut todos, None, "milk");
+        add(&mut todos, None, "bread");
+        todos.delete(id);
+        assert_eq!(store.titles(), ["bread"]);
+
+        assert_eq!(todos.undo().unwrap().as_deref(), Some("delete 'milk'"));
+        assert_eq!(store.titles(), ["milk", "bread"]);
+        assert!(todos.get_trash().is_empty());
+        assert_eq!(todos.undo().unwrap().as_deref(), Some("add 'bread'"));
+        assert_eq!(store.titles(), ["milk"]);
+
+        assert_eq!(todos.redo().unwrap().as_deref(), Some("add 'bread'"));
+        assert_eq!(todos.redo().unwrap().as_deref(), Some("delete 'milk'"));
+        assert_eq!(store.titles(), ["bread"]);
+        assert_eq!(todos.get_trash().len(), 1);
+        assert_eq!(todos.redo(), Ok(None));
+    }
+
+    #[test]
+    fn a_new_command_drops_the_undone_ones() {
+        let (mut todos, store) = new_todos();
+        let id = add(&mut todos, None, "milk");
+        assert!(todos.cycle_status(id).is_ok());
+        assert!(todos.undo().unwrap().is_some());
+        add(&mut todos, None, "bread");
+        assert_eq!(todos.redo(), Ok(None));
+        assert_eq!(store.titles(), ["milk", "bread"]);
+        assert_eq!(status(&mut todos, id), "todo");
     }
 }
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Days, Local};
+use serde::{Deserialize, Serialize};
+use std::{fs, io};
+use uuid::Uuid;
+
+use crate::{store::json::write_atomically, todo::Todo};
+
+/// A deleted todo, along with where it was so that it can be put back
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashEntry {
+    pub todo: Todo,
+    pub deleted_at: DateTime<Local>,
+    /// Todo it was a subtask of, if it still exists when restored
+    pub parent: Option<Uuid>,
+    /// List of the todo, or of its top-level ancestor for a subtask
+    pub list: String,
+}
+
+/// The deleted todos, the most recent last, kept in a file next to the todos until they are
+/// purged
+#[derive(Default)]
+pub struct Trash {
+    entries: Vec<TrashEntry>,
+    /// Where the trash is saved, nothing being saved without one
+    file_path: Option<String>,
+}
+
+impl Trash {
+    /// Reads the trash, which is empty when the file doesn't exist yet. Unreadable data is an
+    /// error rather than an empty trash, which would be written over it
+    pub fn load(file_path: String) -> Result<Trash, String> {
+        let entries = match fs::read_to_string(&file_path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|error| error.to_string())?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error.to_string()),
+        };
+        Ok(Trash {
+            entries,
+            file_path: Some(file_path),
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let file_path = match &self.file_path {
+            Some(file_path) => file_path,
+            None => return Ok(()),
+        };
+        serde_json::to_string_pretty(&self.entries)
+            .map_err(io::Error::other)
+            .and_then(|contents| write_atomically(file_path, contents.as_bytes()))
+            .map_err(|error| format!("couldn't write the trash to '{file_path}': {error}"))
+    }
+
+    pub fn entries(&self) -> &[TrashEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&TrashEntry> {
+        self.entries.iter().find(|entry| entry.todo.id == id)
+    }
+
+    pub fn position(&self, id: Uuid) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.todo.id == id)
+    }
+
+    /// Puts the entry back at its position, or at the end if the trash got shorter since. The
+    /// trash is left as it was if it can't be saved
+    pub fn insert(&mut self, idx: usize, entry: TrashEntry) -> Result<(), String> {
+        let idx = idx.min(self.entries.len());
+        self.entries.insert(idx, entry);
+        self.save().inspect_err(|_| {
+            self.entries.remove(idx);
+        })
+    }
+
+    pub fn remove(&mut self, id: Uuid) -> Result<Option<TrashEntry>, String> {
+        let idx = match self.position(id) {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+        let entry = self.entries.remove(idx);
+        match self.save() {
+            Ok(()) => Ok(Some(entry)),
+            Err(error) => {
+                self.entries.insert(idx, entry);
+                Err(error)
+            }
+        }
+    }
+
+    /// Deletes for good the todos that were deleted more than `days` ago, 0 keeping them
+    /// forever, and returns them. The trash is left as it was if it can't be saved
+    pub fn purge_older_than(
+        &mut self,
+        days: u64,
+        now: DateTime<Local>,
+    ) -> Result<Vec<TrashEntry>, String> {
+        let limit = match now.checked_sub_days(Days::new(days)) {
+            Some(limit) if days > 0 => limit,
+            _ => return Ok(Vec::new()),
+        };
+        let before = self.entries.clone();
+        let (kept, purged) = before
+            .iter()
+            .cloned()
+            .partition(|entry| entry.deleted_at > limit);
+        self.entries = kept;
+        if purged.is_empty() {
+            return Ok(purged);
+        }
+        match self.save() {
+            Ok(()) => Ok(purged),
+            Err(error) => {
+                self.entries = before;
+                Err(error)
+            }
+        }
+    }
+}
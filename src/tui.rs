@@ -1,2 +1,5 @@
+pub mod calendar;
+pub mod confirm;
 pub mod drawing;
 pub mod events_handling;
+pub mod finder;
@@ -0,0 +1,96 @@
+use chrono::{Datelike, Days, NaiveDate};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph, Widget},
+};
+use std::collections::HashSet;
+
+/// Size of the popup, a week being 7 columns of 3 characters and a month at most 6 weeks
+pub const CALENDAR_WIDTH: u16 = 7 * 3 + 3;
+pub const CALENDAR_HEIGHT: u16 = 6 + 5;
+
+const WEEKDAYS_HEADER: &str = "Mo Tu We Th Fr Sa Su";
+
+/// Month grid centered on the selected day, highlighting the days that have todos due
+pub struct Calendar<'a> {
+    pub selected: NaiveDate,
+    pub today: NaiveDate,
+    pub due_days: &'a HashSet<NaiveDate>,
+    pub style: Style,
+    pub border_style: Style,
+}
+
+impl Calendar<'_> {
+    /// The area of the popup centered in `area`
+    pub fn popup_area(area: Rect) -> Rect {
+        let [vertical] = Layout::vertical([Constraint::Length(CALENDAR_HEIGHT)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Length(CALENDAR_WIDTH)])
+            .flex(Flex::Center)
+            .areas(vertical);
+        popup
+    }
+
+    fn day_style(&self, day: NaiveDate) -> Style {
+        let mut style = self.style;
+        if self.due_days.contains(&day) {
+            style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        }
+        if day == self.today {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        if day == self.selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        style
+    }
+
+    fn weeks(&self) -> Vec<Line<'static>> {
+        let first = self.selected.with_day(1).unwrap_or(self.selected);
+        let mut day = first
+            .checked_sub_days(Days::new(first.weekday().num_days_from_monday().into()))
+            .unwrap_or(first);
+
+        let mut weeks = Vec::new();
+        while day.month() == first.month() || day < first {
+            let mut spans = Vec::new();
+            for _ in 0..7 {
+                spans.push(match day.month() == first.month() {
+                    true => Span::styled(format!("{:>2}", day.day()), self.day_style(day)),
+                    false => Span::raw("  "),
+                });
+                spans.push(Span::raw(" "));
+                day = match day.succ_opt() {
+                    Some(next) => next,
+                    None => return weeks,
+                };
+            }
+            weeks.push(Line::from(spans));
+        }
+        weeks
+    }
+}
+
+impl Widget for Calendar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+
+        let mut lines = vec![Line::from(WEEKDAYS_HEADER).bold(), Line::from("")];
+        lines.extend(self.weeks());
+
+        Paragraph::new(lines)
+            .style(self.style)
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(self.border_style)
+                    .title(format!(" {} ", self.selected.format("%B %Y")))
+                    .title_alignment(ratatui::layout::Alignment::Center),
+            )
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,54 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    widgets::{Block, BorderType, Clear, Paragraph, Widget, Wrap},
+};
+
+/// Popup asking the user to confirm an action that can't easily be taken back
+pub struct Confirm<'a> {
+    pub title: &'a str,
+    pub question: &'a str,
+    /// Keys answering the question, shown under it
+    pub keys: &'a str,
+    pub style: Style,
+    pub border_style: Style,
+}
+
+impl Confirm<'_> {
+    /// The area of the popup centered in `area`
+    pub fn popup_area(area: Rect) -> Rect {
+        let [vertical] = Layout::vertical([Constraint::Length(6)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Percentage(50)])
+            .flex(Flex::Center)
+            .areas(vertical);
+        popup
+    }
+}
+
+impl Widget for Confirm<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(self.border_style)
+            .title(format!(" {} ", self.title));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [question, keys] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+        Paragraph::new(self.question)
+            .style(self.style)
+            .centered()
+            .wrap(Wrap { trim: true })
+            .render(question, buf);
+        Paragraph::new(self.keys)
+            .style(self.style)
+            .bold()
+            .centered()
+            .render(keys, buf);
+    }
+}
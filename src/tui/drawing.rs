@@ -1,16 +1,22 @@
+use chrono::Local;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
     style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
     widgets::{block::Title, Block, BorderType, List, Paragraph, Wrap},
     Frame,
 };
 use Constraint::{Length, Percentage};
 
 use crate::{
+    search::{highlight, highlight_positions},
     states::{
-        CreateTab, Screens, States, ALL_KEY_EDIT, MAX_DATE_LEN, MAX_DESCRIPTION_LEN, MAX_TITLE_LEN,
+        CreateTab, Screens, States, ALL_KEY_EDIT, MAX_DATE_LEN, MAX_DESCRIPTION_LEN,
+        MAX_RECURRENCE_LEN, MAX_TAGS_LEN, MAX_TITLE_LEN,
     },
+    tags::tag_chip,
     todo::Todos,
+    tui::{calendar::Calendar, confirm::Confirm, finder::Finder},
 };
 
 const BLOCK: Block = Block::bordered().border_type(BorderType::Rounded);
@@ -21,6 +27,7 @@ const ACTIVE_COLOR: Color = Color::Red;
 const TEXT_STYLE: Style = Style::new().fg(ACTIVE_COLOR);
 
 fn display_main_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
+    states.sync_selection(todos);
     let outer_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![Length(3), Percentage(100), Length(1)])
@@ -39,6 +46,20 @@ fn display_main_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
             .block(CENTERED_BLOCK),
         outer_layout[0],
     );
+    let current_list = String::from(todos.get_list());
+    let list_tabs = Line::from(Vec::from_iter(todos.get_lists().into_iter().map(
+        |(name, nb_open)| {
+            let tab = Span::raw(format!(" {name} {nb_open} "));
+            match name == current_list {
+                true => tab.fg(ACTIVE_COLOR).reversed(),
+                false => tab.fg(BG_COLOR),
+            }
+        },
+    )));
+    frame.render_widget(
+        Paragraph::new(list_tabs).centered(),
+        CENTERED_BLOCK.inner(outer_layout[0]),
+    );
 
     let todos_layout = Layout::default()
         .direction(Direction::Horizontal)
@@ -47,7 +68,22 @@ fn display_main_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
 
     frame.render_stateful_widget(
         List::new(todos.get_todos_titles())
-            .block(BLOCK.title(" TODOs ").fg(BG_COLOR))
+            .block(
+                BLOCK
+                    .title(match todos.get_sort().label() {
+                        Some(label) => format!(" TODOs ({label}) "),
+                        None => String::from(" TODOs "),
+                    })
+                    .title(match todos.get_tag_filter() {
+                        Some(tag) => Line::from(vec![tag_chip(tag), Span::raw(" ")]),
+                        None => Line::from(""),
+                    })
+                    .title_bottom(match todos.get_time_filter() {
+                        Some(filter) => Line::from(format!(" {} ", filter.label())).italic(),
+                        None => Line::from(""),
+                    })
+                    .fg(BG_COLOR),
+            )
             .style(TEXT_STYLE)
             .highlight_style(Style::new().reversed())
             .repeat_highlight_symbol(true),
@@ -55,55 +91,254 @@ fn display_main_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
         states.get_todo_list(),
     );
 
+    let blocking_chain = match states.get_selected_id() {
+        Some(id) => todos.get_blocking_chain(id),
+        None => Vec::new(),
+    };
+    let blocking_chain_height = match blocking_chain.is_empty() {
+        true => 0,
+        false => blocking_chain.len() as u16 + 2,
+    };
     let date_done_contents_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(vec![Length(3), Percentage(100)])
+        .constraints(vec![
+            Length(3),
+            Length(blocking_chain_height),
+            Length(4),
+            Percentage(100),
+        ])
         .split(todos_layout[1]);
 
     let date_done_layout = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints(vec![Percentage(70), Percentage(30)])
+        .constraints(vec![Percentage(50), Percentage(25), Percentage(25)])
         .split(date_done_contents_layout[0]);
 
     let mut description = String::new();
     let mut due_date = String::new();
-    let mut is_done = String::new();
-    if let Some(idx) = states.get_todo_list().selected() {
-        description.push_str(&todos.get_description(idx));
-        due_date.push_str(&todos.get_due_date(idx));
-        is_done.push_str(&todos.is_done(idx));
+    let mut due_style = TEXT_STYLE;
+    let mut status = String::new();
+    let mut priority = Line::from("");
+    let mut tags = Line::from("");
+    let mut history = String::new();
+    if let Some(id) = states.get_selected_id() {
+        // Non-breaking spaces, so that wrapping never splits a timestamp
+        let timestamps = Vec::from_iter(todos.get_timestamps(id).iter().map(|(field, time)| {
+            format!(
+                "{}\u{a0}{}",
+                field.label(),
+                time.format("%Y-%m-%d\u{a0}%H:%M")
+            )
+        }));
+        history = match timestamps.is_empty() {
+            true => String::from("N/A"),
+            false => timestamps.join(" | "),
+        };
+        tags = Line::from(Vec::from_iter(
+            todos
+                .get_tags(id)
+                .iter()
+                .flat_map(|tag| [tag_chip(tag), Span::raw(" ")]),
+        ));
+        let todo_priority = todos.get_priority(id);
+        priority = Line::from(todo_priority.as_str()).fg(todo_priority.color());
+        description.push_str(&todos.get_description(id));
+        due_date.push_str(&todos.get_due_date(id));
+        if let Some(recurrence) = todos.get_recurrence(id) {
+            due_date.push_str(&format!(" ⟳ {recurrence}"));
+        }
+        if let Some(status) = todos.get_due_status(id) {
+            if let (Some(label), Some(color)) = (status.label(), status.color()) {
+                due_date.push_str(&format!(" ({label})"));
+                due_style = due_style.fg(color).bold();
+            }
+        }
+        status.push_str(&todos.get_status(id));
     }
     frame.render_widget(
         Paragraph::new(due_date)
             .centered()
-            .style(TEXT_STYLE)
+            .style(due_style)
             .block(CENTERED_BLOCK.title(" Due Date ").fg(BG_COLOR)),
         date_done_layout[0],
     );
     frame.render_widget(
-        Paragraph::new(is_done)
+        Paragraph::new(priority)
             .centered()
-            .style(TEXT_STYLE)
-            .block(CENTERED_BLOCK.title(" Done ").fg(BG_COLOR)),
+            .block(CENTERED_BLOCK.title(" Priority ").fg(BG_COLOR)),
         date_done_layout[1],
     );
+    frame.render_widget(
+        Paragraph::new(status)
+            .centered()
+            .style(TEXT_STYLE)
+            .block(CENTERED_BLOCK.title(" Status ").fg(BG_COLOR)),
+        date_done_layout[2],
+    );
     frame.render_widget(
         Paragraph::new(description)
             .style(TEXT_STYLE)
             .wrap(Wrap { trim: true })
-            .block(BLOCK.title(" Contents ").fg(BG_COLOR)),
-        date_done_contents_layout[1],
+            .block(BLOCK.title(" Contents ").title_bottom(tags).fg(BG_COLOR)),
+        date_done_contents_layout[3],
     );
-
     frame.render_widget(
-        Paragraph::new("q: quit | t: toggle done | e: edit | d: delete")
+        Paragraph::new(history)
             .centered()
-            .fg(BG_COLOR),
-        outer_layout[2],
+            .wrap(Wrap { trim: true })
+            .style(TEXT_STYLE)
+            .block(CENTERED_BLOCK.title(" History ").fg(BG_COLOR)),
+        date_done_contents_layout[2],
     );
+    if !blocking_chain.is_empty() {
+        frame.render_widget(
+            Paragraph::new(blocking_chain)
+                .style(TEXT_STYLE)
+                .block(BLOCK.title(" Blocked by ").fg(BG_COLOR)),
+            date_done_contents_layout[1],
+        );
+    }
+
+    let pending_completion = states.get_pending_completion();
+    let linking = states.get_linking();
+    let moving = states.get_moving();
+    let new_list_name = states.get_new_list_name().cloned();
+    let search = todos.get_search().cloned();
+    let backups_help = match todos.keeps_backups() {
+        true => " | b: backups",
+        false => "",
+    };
+    // The error of the last action comes first, as the read-only reason stays until the end
+    let footer = if let Some(message) = states.get_error_message() {
+        Paragraph::new(message.as_str()).fg(ACTIVE_COLOR)
+    } else if let Some(reason) = todos.get_read_only_reason() {
+        Paragraph::new(format!("Read-only, {reason} | q: quit{backups_help}")).fg(ACTIVE_COLOR)
+    } else if let Some(notice) = states.get_notice() {
+        Paragraph::new(notice.as_str()).style(TEXT_STYLE)
+    } else if let Some(id) = pending_completion {
+        Paragraph::new(format!(
+            "Also complete the {} open subtasks? y: all | n: only this one | Esc: cancel",
+            todos.count_open_descendants(id)
+        ))
+        .fg(ACTIVE_COLOR)
+    } else if let Some(id) = linking {
+        let title = todos
+            .get_todo(id)
+            .map(|todo| todo.title.clone())
+            .unwrap_or_default();
+        Paragraph::new(format!(
+            "Select the todo '{title}' waits on | Enter: link/unlink | Esc: cancel"
+        ))
+        .fg(ACTIVE_COLOR)
+    } else if let Some(id) = moving {
+        let title = todos
+            .get_todo(id)
+            .map(|todo| todo.title.clone())
+            .unwrap_or_default();
+        Paragraph::new(format!(
+            "Show the list to move '{title}' to | Tab/Shift+Tab: switch list | Enter: move | Esc: cancel"
+        ))
+        .fg(ACTIVE_COLOR)
+    } else if let Some(name) = new_list_name {
+        Paragraph::new(format!("New list: {name}_ | Enter: create | Esc: cancel")).fg(ACTIVE_COLOR)
+    } else if let Some(query) = search {
+        let footer = match states.is_searching() {
+            true => format!("/{query}_ | Enter: browse the hits | Esc: cancel"),
+            false => format!(
+                "Search '{query}': {} hits | n/N: next/previous hit | /: edit | Esc: clear",
+                todos.get_search_hits().len()
+            ),
+        };
+        Paragraph::new(footer).fg(ACTIVE_COLOR)
+    } else {
+        Paragraph::new(format!(
+            "q: quit | a: add | A: subtask | t: status | e: edit | d: delete | T: trash | x/X: archive | u/^R: undo/redo | l: link | J/K: move | ←/→: fold | Tab: list | n/m: new/move | /: search | ^P: find | s/S: sort/reverse | z: done last | f/F: tag | w/W: when{backups_help}",
+        ))
+        .fg(BG_COLOR)
+    };
+    frame.render_widget(footer.centered(), outer_layout[2]);
+
+    if let Some(query) = states.get_finder_query().cloned() {
+        let selected = states.get_finder_selected();
+        let hits = states.get_finder_hits();
+        let mut results = Vec::new();
+        for hit in hits {
+            if let Some(todo) = todos.get_todo(hit.id) {
+                let mut spans = highlight_positions(&todo.title, &hit.title_positions, TEXT_STYLE);
+                spans.push(Span::raw(format!("  {}", todo.list)).fg(BG_COLOR).italic());
+                results.push(Line::from(spans));
+            }
+        }
+        let preview = hits
+            .get(selected)
+            .map(|hit| todos.get_description(hit.id))
+            .unwrap_or_default();
+        frame.render_widget(
+            Finder {
+                query: &query,
+                results,
+                selected,
+                preview: &preview,
+                style: TEXT_STYLE,
+                border_style: Style::new().fg(ACTIVE_COLOR),
+            },
+            Finder::popup_area(frame.area()),
+        );
+    }
+
+    if let Some(id) = states.get_pending_deletion() {
+        let question = match todos.get_todo(id) {
+            Some(todo) if todo.children.is_empty() => {
+                format!("Move '{}' to the trash?", todo.title_or_placeholder())
+            }
+            Some(todo) => format!(
+                "Move '{}' and its subtasks to the trash?",
+                todo.title_or_placeholder()
+            ),
+            None => String::new(),
+        };
+        frame.render_widget(
+            Confirm {
+                title: "Delete",
+                question: &question,
+                keys: "y/Enter: delete | n/Esc: cancel",
+                style: TEXT_STYLE,
+                border_style: Style::new().fg(ACTIVE_COLOR),
+            },
+            Confirm::popup_area(frame.area()),
+        );
+    }
+
+    if states.is_pending_archive() {
+        let days = states.get_archive_days();
+        let nb_all = todos.get_archivable(None).len();
+        let nb_old = todos.get_archivable(Some(days)).len();
+        let question = format!(
+            "Archive the {nb_all} done todos, or only the {nb_old} done {days} days ago or more?"
+        );
+        frame.render_widget(
+            Confirm {
+                title: "Archive",
+                question: &question,
+                keys: "y: all | o: the oldest | n/Esc: cancel",
+                style: TEXT_STYLE,
+                border_style: Style::new().fg(ACTIVE_COLOR),
+            },
+            Confirm::popup_area(frame.area()),
+        );
+    }
 }
 
-fn display_create_ui(frame: &mut Frame, states: &mut States) {
+/// Text of the input of the tab, with its cursor and selection while it is being written
+fn input_text(states: &mut States, tab: CreateTab) -> Text<'static> {
+    let focused = states.is_in_writting_mode() && states.is_selected_tab(tab);
+    states
+        .get_input(tab)
+        .map(|input| input.to_text(Style::default(), focused))
+        .unwrap_or_default()
+}
+
+fn display_create_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
     let horizontal_layout = Layout::default()
         .direction(Direction::Horizontal)
         .constraints(vec![Percentage(70)])
@@ -111,19 +346,27 @@ fn display_create_ui(frame: &mut Frame, states: &mut States) {
         .split(frame.area());
     let vertical_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(vec![Length(4), Percentage(100), Length(1), Length(1)])
+        .constraints(vec![
+            Length(4),
+            Length(1),
+            Length(3),
+            Length(1),
+            Percentage(100),
+            Length(1),
+            Length(1),
+        ])
         .flex(ratatui::layout::Flex::Start)
         .vertical_margin(5)
         .split(horizontal_layout[0]);
 
     let title_date_done_layout = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints(vec![Percentage(50), Percentage(50)])
+        .constraints(vec![Percentage(40), Percentage(40), Percentage(20)])
         .split(vertical_layout[0]);
 
     frame.render_widget(
-        Paragraph::new(String::from(states.get_title()))
-            .wrap(Wrap { trim: true })
+        Paragraph::new(input_text(states, CreateTab::Title))
+            .wrap(Wrap { trim: false })
             .block(
                 BLOCK
                     .title(" Title ")
@@ -140,8 +383,8 @@ fn display_create_ui(frame: &mut Frame, states: &mut States) {
         title_date_done_layout[0],
     );
     frame.render_widget(
-        Paragraph::new(String::from(states.get_date()))
-            .wrap(Wrap { trim: true })
+        Paragraph::new(input_text(states, CreateTab::Date))
+            .wrap(Wrap { trim: false })
             .block(
                 BLOCK
                     .title(" Due Date ")
@@ -158,9 +401,77 @@ fn display_create_ui(frame: &mut Frame, states: &mut States) {
         title_date_done_layout[1],
     );
 
+    // Preview of the date the input resolves to
+    let date_preview = match states.parse_date() {
+        Ok(Some(due_date)) => Line::from(format!("→ {} {due_date}", due_date.date.format("%a"))),
+        Ok(None) => Line::from(""),
+        Err(_) => Line::from("→ ?").fg(ACTIVE_COLOR),
+    };
     frame.render_widget(
-        Paragraph::new(String::from(states.get_description()))
-            .wrap(Wrap { trim: true })
+        Paragraph::new(date_preview).fg(BG_COLOR),
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Percentage(40), Percentage(40), Percentage(20)])
+            .split(vertical_layout[1])[1],
+    );
+
+    let priority = states.get_priority();
+    frame.render_widget(
+        Paragraph::new(Line::from(format!("◀ {} ▶", priority.as_str())).fg(priority.color()))
+            .centered()
+            .block(BLOCK.title(" Priority ").style(TEXT_STYLE).border_style(
+                Style::default().fg(states.get_fg_color_for_tab(CreateTab::Priority)),
+            )),
+        title_date_done_layout[2],
+    );
+
+    let tags_recurrence_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Percentage(60), Percentage(40)])
+        .split(vertical_layout[2]);
+    frame.render_widget(
+        Paragraph::new(input_text(states, CreateTab::Tags)).block(
+            BLOCK
+                .title(" Tags ")
+                .title(Title::from(format!(
+                    " {}/{} ",
+                    states.get_nb_char_in_tab(CreateTab::Tags),
+                    MAX_TAGS_LEN,
+                )))
+                .style(TEXT_STYLE)
+                .border_style(Style::default().fg(states.get_fg_color_for_tab(CreateTab::Tags))),
+        ),
+        tags_recurrence_layout[0],
+    );
+    frame.render_widget(
+        Paragraph::new(input_text(states, CreateTab::Recurrence)).block(
+            BLOCK
+                .title(" Repeat ")
+                .title(Title::from(format!(
+                    " {}/{} ",
+                    states.get_nb_char_in_tab(CreateTab::Recurrence),
+                    MAX_RECURRENCE_LEN,
+                )))
+                .style(TEXT_STYLE)
+                .border_style(
+                    Style::default().fg(states.get_fg_color_for_tab(CreateTab::Recurrence)),
+                ),
+        ),
+        tags_recurrence_layout[1],
+    );
+    let suggestions = states.get_tag_suggestions(&todos.get_all_tags());
+    if states.is_selected_tab(CreateTab::Tags) && !suggestions.is_empty() {
+        let mut spans = vec![Span::raw("Tab: ").fg(BG_COLOR)];
+        for suggestion in suggestions {
+            spans.push(tag_chip(&suggestion));
+            spans.push(Span::raw(" "));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), vertical_layout[3]);
+    }
+
+    frame.render_widget(
+        Paragraph::new(input_text(states, CreateTab::Description))
+            .wrap(Wrap { trim: false })
             .block(
                 BLOCK
                     .title(" Description ")
@@ -177,12 +488,24 @@ fn display_create_ui(frame: &mut Frame, states: &mut States) {
         Layout::default()
             .direction(Direction::Horizontal)
             .constraints(vec![Percentage(100)])
-            .split(vertical_layout[1])[0],
+            .split(vertical_layout[4])[0],
     );
 
     frame.render_widget(
         Paragraph::new(match states.is_in_writting_mode() {
-            true => String::from("Esc: exit writting mode"),
+            true => String::from(
+                "Esc: exit writting mode | ←/→ Home/End: move | ^←/^→: word | Shift: select | ^A: all | Del: delete",
+            ),
+            false if states.get_calendar().is_some() => String::from(
+                "Esc: close | Arrows: move day | PgUp/PgDn: move month | t: today | Enter: pick",
+            ),
+            false if states.is_selected_tab(CreateTab::Priority) => {
+                String::from("q/Esc: quit | Tab: cycle tab | Left/Right: change priority | a: save")
+            }
+            false if states.is_selected_tab(CreateTab::Date) => format!(
+                "q/Esc: quit | Tab: cycle tab | {}: edit | c: calendar | a: save",
+                ALL_KEY_EDIT
+            ),
             false => format!(
                 "q/Esc: quit | Tab: cycle tab | {}: edit | a: save",
                 ALL_KEY_EDIT
@@ -190,8 +513,28 @@ fn display_create_ui(frame: &mut Frame, states: &mut States) {
         })
         .centered()
         .fg(BG_COLOR),
-        vertical_layout[2],
+        vertical_layout[5],
     );
+    if let Some(message) = states.get_error_message() {
+        frame.render_widget(
+            Paragraph::new(message.as_str()).centered().fg(ACTIVE_COLOR),
+            vertical_layout[6],
+        );
+    }
+
+    if let Some(selected) = states.get_calendar() {
+        let due_days = todos.get_due_days();
+        frame.render_widget(
+            Calendar {
+                selected,
+                today: Local::now().date_naive(),
+                due_days: &due_days,
+                style: TEXT_STYLE,
+                border_style: Style::new().fg(ACTIVE_COLOR),
+            },
+            Calendar::popup_area(frame.area()),
+        );
+    }
     /*frame.render_widget(
         Paragraph::new(match states.is_in_writting_mode() {
             true => String::from("Esc: exit writting mode"),
@@ -199,14 +542,227 @@ fn display_create_ui(frame: &mut Frame, states: &mut States) {
         })
         .centered()
         .fg(BG_COLOR),
-        vertical_layout[3],
+        vertical_layout[6],
     );*/
 }
 
+fn display_backups_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Percentage(100), Length(1)])
+        .split(frame.area());
+
+    let backups = todos.get_backups();
+    let items = Vec::from_iter(backups.iter().map(|backup| {
+        backup
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }));
+    let title = match items.is_empty() {
+        true => String::from(" Backups (none) "),
+        false => format!(" Backups ({}) ", items.len()),
+    };
+
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(BLOCK.title(title).fg(BG_COLOR))
+            .style(TEXT_STYLE)
+            .highlight_style(Style::new().reversed()),
+        vertical_layout[0],
+        states.get_backup_list(),
+    );
+
+    frame.render_widget(
+        help_or_error(
+            states,
+            "q/Esc: back | Enter: restore (the current todos are backed up first)",
+        ),
+        vertical_layout[1],
+    );
+}
+
+fn display_trash_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Percentage(100), Length(1)])
+        .split(frame.area());
+
+    let items = Vec::from_iter(todos.get_trash().iter().rev().map(|entry| {
+        let mut spans = vec![Span::raw(entry.todo.title_or_placeholder())];
+        if !entry.todo.children.is_empty() {
+            spans.push(Span::raw(" (with its subtasks)"));
+        }
+        spans.push(
+            Span::raw(format!(
+                "  {}, deleted on {}",
+                entry.list,
+                entry.deleted_at.format("%Y-%m-%d %H:%M")
+            ))
+            .fg(BG_COLOR)
+            .italic(),
+        );
+        Line::from(spans)
+    }));
+    let title = match items.is_empty() {
+        true => String::from(" Trash (empty) "),
+        false => format!(" Trash ({}) ", items.len()),
+    };
+
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(BLOCK.title(title).fg(BG_COLOR))
+            .style(TEXT_STYLE)
+            .highlight_style(Style::new().reversed()),
+        vertical_layout[0],
+        states.get_trash_list(),
+    );
+
+    frame.render_widget(
+        help_or_error(
+            states,
+            "q/Esc: back | Enter/r: restore | d: delete for good",
+        ),
+        vertical_layout[1],
+    );
+
+    if let Some(id) = states.get_pending_purge() {
+        let question = match todos.get_trash().iter().find(|entry| entry.todo.id == id) {
+            Some(entry) => format!("Delete '{}' for good?", entry.todo.title_or_placeholder()),
+            None => String::new(),
+        };
+        frame.render_widget(
+            Confirm {
+                title: "Purge",
+                question: &question,
+                keys: "y/Enter: delete | n/Esc: cancel",
+                style: TEXT_STYLE,
+                border_style: Style::new().fg(ACTIVE_COLOR),
+            },
+            Confirm::popup_area(frame.area()),
+        );
+    }
+}
+
+fn display_archive_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Percentage(100), Length(1)])
+        .split(frame.area());
+
+    let query = states.get_archive_search().clone();
+    let nb_archived = todos.get_archive().len();
+    let archived = todos.search_archive(&query);
+    let nb_hits = archived.len();
+    let items = Vec::from_iter(archived.into_iter().map(|todo| {
+        let mut spans = highlight(&todo.title_or_placeholder(), &query, TEXT_STYLE);
+        if !todo.children.is_empty() {
+            spans.push(Span::raw(" (with its subtasks)"));
+        }
+        let done = todo
+            .completed_at
+            .map(|completed_at| format!(", done on {}", completed_at.format("%Y-%m-%d")))
+            .unwrap_or_default();
+        spans.push(
+            Span::raw(format!("  {}{done}", todo.list))
+                .fg(BG_COLOR)
+                .italic(),
+        );
+        Line::from(spans)
+    }));
+    let title = match (nb_archived, query.is_empty()) {
+        (0, _) => String::from(" Archive (empty) "),
+        (_, true) => format!(" Archive ({nb_archived}) "),
+        (_, false) => format!(" Archive ({nb_hits} of {nb_archived} matching '{query}') "),
+    };
+
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(BLOCK.title(title).fg(BG_COLOR))
+            .style(TEXT_STYLE)
+            .highlight_style(Style::new().reversed()),
+        vertical_layout[0],
+        states.get_archive_list(),
+    );
+
+    let footer = match states.is_searching_archive() {
+        true => format!("/{query}_ | Enter: browse the hits | Esc: cancel"),
+        false => String::from("q/Esc: back | Enter/r: unarchive | /: search"),
+    };
+    frame.render_widget(help_or_error(states, &footer), vertical_layout[1]);
+}
+
+fn display_recovery_ui(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Length(9), Percentage(100), Length(1)])
+        .split(frame.area());
+
+    let location = todos.get_location();
+    let mut error_lines = vec![
+        Line::from(format!(
+            "The todos in '{location}' could not be read, nothing will be written to it until you choose what to do."
+        )),
+        Line::from(""),
+    ];
+    if let Some(error) = todos.get_load_error() {
+        error_lines.push(Line::from(format!("Error: {}", error.message)).fg(ACTIVE_COLOR));
+        error_lines.push(Line::from(""));
+        error_lines.push(Line::from(format!("{:>5} | {}", error.line, error.excerpt)));
+        error_lines.push(Line::from(format!(
+            "{:>5} | {}^",
+            "",
+            " ".repeat(error.column.saturating_sub(1))
+        )));
+    }
+    frame.render_widget(
+        Paragraph::new(error_lines)
+            .wrap(Wrap { trim: false })
+            .block(BLOCK.title(" Recovery (read-only) ").fg(BG_COLOR)),
+        vertical_layout[0],
+    );
+
+    let help = match todos.get_salvaged_titles() {
+        Some(titles) => {
+            let nb_titles = titles.len();
+            frame.render_widget(
+                List::new(titles)
+                    .style(TEXT_STYLE)
+                    .block(BLOCK.title(" Salvaged todos ").fg(BG_COLOR)),
+                vertical_layout[1],
+            );
+            format!(
+                "y: overwrite the file with these {} todos (a copy of it is kept) | n/Esc: cancel",
+                nb_titles
+            )
+        }
+        None => format!(
+            "q: quit | o: open the raw file{} | s: salvage the valid todos",
+            match todos.keeps_backups() {
+                true => " | b: restore a backup",
+                false => "",
+            }
+        ),
+    };
+    frame.render_widget(help_or_error(states, &help), vertical_layout[2]);
+}
+
+/// Footer showing the help of the screen, unless an error has to be shown instead
+fn help_or_error(states: &mut States, help: &str) -> Paragraph<'static> {
+    match states.get_error_message() {
+        Some(message) => Paragraph::new(message.clone()).centered().fg(ACTIVE_COLOR),
+        None => Paragraph::new(String::from(help)).centered().fg(BG_COLOR),
+    }
+}
+
 pub fn draw(frame: &mut Frame, states: &mut States, todos: &mut Todos) {
     match states.get_screen() {
         Screens::Main => display_main_ui(frame, states, todos),
-        Screens::Create => display_create_ui(frame, states),
-        Screens::Edit => display_create_ui(frame, states),
+        Screens::Create => display_create_ui(frame, states, todos),
+        Screens::Edit => display_create_ui(frame, states, todos),
+        Screens::Backups => display_backups_ui(frame, states, todos),
+        Screens::Recovery => display_recovery_ui(frame, states, todos),
+        Screens::Trash => display_trash_ui(frame, states, todos),
+        Screens::Archive => display_archive_ui(frame, states, todos),
     }
 }
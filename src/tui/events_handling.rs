@@ -1,44 +1,106 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
 use crate::{
-    states::{Screens, States, ALL_KEY_EDIT},
+    states::{CreateTab, Screens, States, ALL_KEY_EDIT, MAX_SEARCH_LEN},
     todo::Todos,
 };
+use uuid::Uuid;
+
+fn handle_calendar_events(states: &mut States, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => states.close_calendar(),
+        KeyCode::Left => states.move_calendar_days(-1),
+        KeyCode::Right => states.move_calendar_days(1),
+        KeyCode::Up => states.move_calendar_days(-7),
+        KeyCode::Down => states.move_calendar_days(7),
+        KeyCode::PageUp => states.move_calendar_months(-1),
+        KeyCode::PageDown => states.move_calendar_months(1),
+        KeyCode::Char('t') => states.move_calendar_to_today(),
+        KeyCode::Enter => states.pick_calendar_date(),
+        _ => {}
+    }
+}
+
+/// Edits the text of the selected tab at its cursor, Shift selecting and Control jumping words
+fn handle_writting_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    let select = key.modifiers.contains(KeyModifiers::SHIFT);
+    // AltGr comes as Control and Alt, and types characters rather than being a shortcut
+    let by_word =
+        key.modifiers.contains(KeyModifiers::CONTROL) && !key.modifiers.contains(KeyModifiers::ALT);
+    if key.code == KeyCode::Esc {
+        states.set_writting_mode(false);
+        return;
+    }
+    if key.code == KeyCode::Tab && states.is_selected_tab(CreateTab::Tags) {
+        states.complete_tag(&todos.get_all_tags());
+        return;
+    }
+    let multiline = states.is_selected_tab(CreateTab::Description);
+    let input = match states.get_selected_input() {
+        Some(input) => input,
+        None => return,
+    };
+    match key.code {
+        KeyCode::Char('a') if by_word => input.select_all(),
+        KeyCode::Char(c) if !by_word => input.insert_char(c),
+        KeyCode::Tab => input.insert_char('\t'),
+        KeyCode::Enter if multiline => input.insert_char('\n'),
+        KeyCode::Backspace => input.backspace(),
+        KeyCode::Delete => input.delete(),
+        KeyCode::Left if by_word => input.move_word_left(select),
+        KeyCode::Right if by_word => input.move_word_right(select),
+        KeyCode::Left => input.move_left(select),
+        KeyCode::Right => input.move_right(select),
+        KeyCode::Home => input.move_home(select),
+        KeyCode::End => input.move_end(select),
+        _ => {}
+    }
+}
 
 fn handle_create_ui_events(todos: &mut Todos, states: &mut States, key: KeyEvent, edit: bool) {
-    if states.is_in_writting_mode() {
-        if key.code == KeyCode::Esc {
-            states.set_writting_mode(false);
-        } else if key.code == KeyCode::Backspace {
-            states.pop_char();
-        } else {
-            match key.code.to_string().as_str() {
-                "Space" => states.add_char(' '),
-                "Tab" => states.add_char('\t'),
-                _ => {
-                    if let Some(c) = key.code.to_string().chars().next() {
-                        states.add_char(c);
-                    }
-                }
-            }
-        }
+    if states.get_calendar().is_some() {
+        handle_calendar_events(states, key);
+    } else if states.is_in_writting_mode() {
+        handle_writting_events(todos, states, key);
     } else {
         match key.code {
-            KeyCode::Esc | KeyCode::Char('q') => states.set_screen(Screens::Main),
-            KeyCode::Char(ALL_KEY_EDIT) => states.set_writting_mode(true),
+            KeyCode::Esc | KeyCode::Char('q') => {
+                states.clear_strings();
+                states.set_screen(Screens::Main);
+            }
+            KeyCode::Char(ALL_KEY_EDIT) if !states.is_selected_tab(CreateTab::Priority) => {
+                states.set_writting_mode(true)
+            }
             KeyCode::Tab => states.next_tab(),
+            KeyCode::Char('c') if states.is_selected_tab(CreateTab::Date) => states.open_calendar(),
+            KeyCode::Right | KeyCode::Up if states.is_selected_tab(CreateTab::Priority) => {
+                states.raise_priority()
+            }
+            KeyCode::Left | KeyCode::Down if states.is_selected_tab(CreateTab::Priority) => {
+                states.lower_priority()
+            }
             KeyCode::Char('a') => {
+                let due_date = match states.parse_date() {
+                    Ok(due_date) => due_date,
+                    Err(message) => {
+                        states.set_error_message(message);
+                        return;
+                    }
+                };
+                let recurrence = match states.parse_recurrence() {
+                    Ok(recurrence) => recurrence,
+                    Err(message) => {
+                        states.set_error_message(message);
+                        return;
+                    }
+                };
                 if edit {
-                    if let Some(idx) = states.get_todo_list().selected() {
-                        todos.edit(idx, states);
+                    if let Some(id) = states.get_selected_id() {
+                        todos.edit(id, states, due_date, recurrence);
                     }
                 } else {
-                    todos.add(
-                        states.get_title().to_owned(),
-                        states.get_description().to_owned(),
-                        states.get_date().to_owned(),
-                        false,
-                    );
+                    let parent = states.get_new_todo_parent();
+                    todos.add(parent, states, due_date, recurrence);
                 }
                 states.clear_strings();
                 states.set_screen(Screens::Main);
@@ -48,22 +110,521 @@ fn handle_create_ui_events(todos: &mut Todos, states: &mut States, key: KeyEvent
     }
 }
 
+/// Screen to go back to when leaving the backups, as they can be reached from the recovery screen
+fn main_or_recovery_screen(todos: &mut Todos) -> Screens {
+    match todos.get_load_error() {
+        Some(_) => Screens::Recovery,
+        None => Screens::Main,
+    }
+}
+
+fn handle_backups_ui_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => states.set_screen(main_or_recovery_screen(todos)),
+        KeyCode::Down => states.scroll_backups_down(todos.get_backups().len()),
+        KeyCode::Up => states.scroll_backups_up(),
+        KeyCode::Enter => {
+            if let Some(idx) = states.get_backup_list().selected() {
+                if let Some(backup) = todos.get_backups().get(idx) {
+                    match todos.restore_backup(backup) {
+                        Ok(()) => {
+                            states.select_first(todos);
+                            states.set_screen(main_or_recovery_screen(todos));
+                        }
+                        Err(error) => states.set_error_message(error),
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The selected entry of the trash, which is listed the most recent first
+fn selected_trash_entry(todos: &mut Todos, states: &mut States) -> Option<Uuid> {
+    let idx = states.get_trash_list().selected()?;
+    todos
+        .get_trash()
+        .iter()
+        .rev()
+        .nth(idx)
+        .map(|entry| entry.todo.id)
+}
+
+fn handle_trash_ui_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    if let Some(id) = states.get_pending_purge() {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => todos.purge(id),
+            KeyCode::Char('n') | KeyCode::Esc => {}
+            _ => return,
+        }
+        states.set_pending_purge(None);
+        return;
+    }
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => states.set_screen(Screens::Main),
+        KeyCode::Down => states.scroll_trash_down(todos.get_trash().len()),
+        KeyCode::Up => states.scroll_trash_up(),
+        KeyCode::Enter | KeyCode::Char('r') => {
+            if let Some(id) = selected_trash_entry(todos, states) {
+                todos.restore_from_trash(id);
+            }
+        }
+        KeyCode::Char('d') => {
+            if let Some(id) = selected_trash_entry(todos, states) {
+                states.set_pending_purge(Some(id));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The selected todo of the archive, among those matching the search
+fn selected_archived(todos: &mut Todos, states: &mut States) -> Option<Uuid> {
+    let idx = states.get_archive_list().selected()?;
+    let query = states.get_archive_search().clone();
+    todos.search_archive(&query).get(idx).map(|todo| todo.id)
+}
+
+fn handle_archive_ui_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    if states.is_searching_archive() {
+        match key.code {
+            KeyCode::Enter => states.set_searching_archive(false),
+            KeyCode::Esc => {
+                states.clear_archive_search();
+                states.set_searching_archive(false);
+            }
+            KeyCode::Backspace => states.pop_archive_search(),
+            KeyCode::Char(c) => states.push_archive_search(c),
+            _ => {}
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Esc if !states.get_archive_search().is_empty() => states.clear_archive_search(),
+        KeyCode::Esc | KeyCode::Char('q') => states.set_screen(Screens::Main),
+        KeyCode::Down => {
+            let query = states.get_archive_search().clone();
+            states.scroll_archive_down(todos.search_archive(&query).len());
+        }
+        KeyCode::Up => states.scroll_archive_up(),
+        KeyCode::Char('/') => states.set_searching_archive(true),
+        KeyCode::Enter | KeyCode::Char('r') => {
+            if let Some(id) = selected_archived(todos, states) {
+                todos.unarchive(id);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_recovery_ui_events(
+    todos: &mut Todos,
+    states: &mut States,
+    key: KeyEvent,
+) -> std::io::Result<bool> {
+    if todos.get_salvaged_titles().is_some() {
+        match key.code {
+            KeyCode::Char('y') => match todos.confirm_salvage() {
+                Ok(()) => {
+                    states.select_first(todos);
+                    states.set_screen(Screens::Main);
+                }
+                Err(error) => states.set_error_message(error),
+            },
+            KeyCode::Char('n') | KeyCode::Esc => todos.cancel_salvage(),
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    match key.code {
+        KeyCode::Char('q') => return Ok(true),
+        KeyCode::Char('o') => states.request_editor(),
+        KeyCode::Char('b') if todos.keeps_backups() => {
+            states.get_backup_list().select_first();
+            states.set_screen(Screens::Backups);
+        }
+        KeyCode::Char('s') => todos.prepare_salvage(),
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Answer to whether completing a parent also completes its open subtasks
+fn handle_pending_completion_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    if let Some(id) = states.get_pending_completion() {
+        let result = match key.code {
+            KeyCode::Char('y') => todos.complete_with_children(id),
+            KeyCode::Char('n') => todos.cycle_status(id),
+            KeyCode::Esc => Ok(()),
+            _ => return,
+        };
+        if let Err(message) = result {
+            states.set_error_message(message);
+        }
+        states.set_pending_completion(None);
+    }
+}
+
+/// Answer to whether the todo is moved to the trash
+fn handle_pending_deletion_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    if let Some(id) = states.get_pending_deletion() {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => todos.delete(id),
+            KeyCode::Char('n') | KeyCode::Esc => {}
+            _ => return,
+        }
+        states.set_pending_deletion(None);
+    }
+}
+
+/// Answer to which done todos are archived, all of them or only the oldest
+fn handle_pending_archive_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    let archived = match key.code {
+        KeyCode::Char('y') => todos.archive_closed(None),
+        KeyCode::Char('o') => todos.archive_closed(Some(states.get_archive_days())),
+        KeyCode::Char('n') | KeyCode::Esc => 0,
+        _ => return,
+    };
+    if archived > 0 {
+        states.set_notice(Some(format!(
+            "Archived {archived} todos | X: see the archive"
+        )));
+    }
+    states.set_pending_archive(false);
+}
+
+/// Picking the todo that blocks the one being linked
+fn handle_linking_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    if let Some(id) = states.get_linking() {
+        match key.code {
+            KeyCode::Down => states.scroll_down(todos),
+            KeyCode::Up => states.scroll_up(todos),
+            KeyCode::Enter => {
+                if let Some(blocker) = states.get_selected_id() {
+                    if let Err(message) = todos.toggle_blocker(id, blocker) {
+                        states.set_error_message(message);
+                    }
+                }
+                states.set_linking(None);
+                states.select_id(todos, id);
+            }
+            KeyCode::Esc => {
+                states.set_linking(None);
+                states.select_id(todos, id);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Showing the list the todo being moved goes to
+fn handle_moving_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    if let Some(id) = states.get_moving() {
+        match key.code {
+            KeyCode::Tab => todos.cycle_list(1),
+            KeyCode::BackTab => todos.cycle_list(-1),
+            KeyCode::Enter => {
+                todos.move_to_list(id);
+                states.set_moving(None);
+                states.select_id(todos, id);
+            }
+            KeyCode::Esc => states.set_moving(None),
+            _ => {}
+        }
+    }
+}
+
+fn handle_new_list_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    match key.code {
+        KeyCode::Enter => {
+            if let Some(name) = states.get_new_list_name() {
+                todos.add_list(&name.clone());
+            }
+            states.set_new_list_name(None);
+        }
+        KeyCode::Esc => states.set_new_list_name(None),
+        KeyCode::Backspace => states.pop_new_list_name(),
+        KeyCode::Char(c) => states.push_new_list_name(c),
+        _ => {}
+    }
+}
+
+/// Searches the query, selecting the first hit unless the selected todo is one
+fn update_search(todos: &mut Todos, states: &mut States, query: String) {
+    todos.set_search(Some(query));
+    let hits = todos.get_search_hits();
+    let selected_row = states.get_selected_id().and_then(|id| todos.get_row(id));
+    if !selected_row.is_some_and(|row| hits.contains(&row)) {
+        if let Some(&hit) = hits.first() {
+            states.select_row(todos, hit);
+        }
+    }
+}
+
+/// Selects the next search hit after the selected row, or the previous one, going around the
+/// list
+fn jump_to_hit(todos: &mut Todos, states: &mut States, forward: bool) {
+    let hits = todos.get_search_hits();
+    let row = states.get_todo_list().selected().unwrap_or(0);
+    let hit = match forward {
+        true => hits.iter().find(|&&hit| hit > row).or(hits.first()),
+        false => hits.iter().rev().find(|&&hit| hit < row).or(hits.last()),
+    };
+    if let Some(&hit) = hit {
+        states.select_row(todos, hit);
+    }
+}
+
+fn handle_search_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    let mut query = todos.get_search().cloned().unwrap_or_default();
+    match key.code {
+        KeyCode::Enter => {
+            states.set_searching(false);
+            if query.is_empty() {
+                todos.set_search(None);
+            }
+        }
+        KeyCode::Esc => {
+            todos.set_search(None);
+            states.cancel_search(todos);
+        }
+        KeyCode::Backspace => {
+            query.pop();
+            update_search(todos, states, query);
+        }
+        KeyCode::Char(c) if query.len() + c.len_utf8() <= MAX_SEARCH_LEN => {
+            query.push(c);
+            update_search(todos, states, query);
+        }
+        _ => {}
+    }
+}
+
+fn handle_finder_events(todos: &mut Todos, states: &mut States, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => states.close_finder(),
+        KeyCode::Enter => {
+            let selected = states.get_finder_selected();
+            if let Some(id) = states.get_finder_hits().get(selected).map(|hit| hit.id) {
+                todos.reveal(id);
+                states.select_id(todos, id);
+            }
+            states.close_finder();
+        }
+        KeyCode::Down => states.scroll_finder_down(),
+        KeyCode::Char('n') if ctrl => states.scroll_finder_down(),
+        KeyCode::Up => states.scroll_finder_up(),
+        KeyCode::Char('p') if ctrl => states.scroll_finder_up(),
+        KeyCode::Backspace => states.pop_finder_query(todos),
+        KeyCode::Char(c) if !ctrl => states.push_finder_query(todos, c),
+        _ => {}
+    }
+}
+
+/// Moves the selected todo among its siblings, the selection following it
+fn move_selected(todos: &mut Todos, states: &mut States, up: bool) {
+    if let Some(id) = states.get_selected_id() {
+        if let Err(message) = todos.move_todo(id, up) {
+            states.set_error_message(message);
+        }
+    }
+}
+
 fn handle_main_ui_events(
     todos: &mut Todos,
     states: &mut States,
     key: KeyEvent,
 ) -> std::io::Result<bool> {
+    states.set_notice(None);
+    if states.get_pending_completion().is_some() {
+        handle_pending_completion_events(todos, states, key);
+        return Ok(false);
+    }
+    if states.get_pending_deletion().is_some() {
+        handle_pending_deletion_events(todos, states, key);
+        return Ok(false);
+    }
+    if states.is_pending_archive() {
+        handle_pending_archive_events(todos, states, key);
+        return Ok(false);
+    }
+    if states.get_linking().is_some() {
+        handle_linking_events(todos, states, key);
+        return Ok(false);
+    }
+    if states.get_moving().is_some() {
+        handle_moving_events(todos, states, key);
+        return Ok(false);
+    }
+    if states.get_new_list_name().is_some() {
+        handle_new_list_events(todos, states, key);
+        return Ok(false);
+    }
+    if states.is_searching() {
+        handle_search_events(todos, states, key);
+        return Ok(false);
+    }
+    if states.get_finder_query().is_some() {
+        handle_finder_events(todos, states, key);
+        return Ok(false);
+    }
+    // While searching, `n` jumps to the next hit instead of creating a list
+    let creates_list = key.code == KeyCode::Char('n') && todos.get_search().is_none();
+    let moves_todo = key.modifiers.contains(KeyModifiers::SHIFT)
+        && matches!(key.code, KeyCode::Up | KeyCode::Down);
+    let redoes = key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('r');
+    if todos.is_read_only()
+        && (creates_list
+            || moves_todo
+            || redoes
+            || matches!(
+                key.code,
+                KeyCode::Char(
+                    'a' | 'A'
+                        | 't'
+                        | 'd'
+                        | 'T'
+                        | 'x'
+                        | 'X'
+                        | 'u'
+                        | 'l'
+                        | 'm'
+                        | 'J'
+                        | 'K'
+                        | ALL_KEY_EDIT
+                ) | KeyCode::Left
+                    | KeyCode::Right
+            ))
+    {
+        return Ok(false);
+    }
+
     match key.code {
         KeyCode::Char('q') => return Ok(true),
-        KeyCode::Char('a') => states.set_screen(Screens::Create),
+        KeyCode::Char('a') => {
+            states.set_new_todo_parent(None);
+            states.set_screen(Screens::Create);
+        }
+        KeyCode::Char('A') => {
+            if let Some(id) = states.get_selected_id() {
+                states.set_new_todo_parent(Some(id));
+                states.set_screen(Screens::Create);
+            }
+        }
+        KeyCode::Char('J') => move_selected(todos, states, false),
+        KeyCode::Char('K') => move_selected(todos, states, true),
+        KeyCode::Down if moves_todo => move_selected(todos, states, false),
+        KeyCode::Up if moves_todo => move_selected(todos, states, true),
         KeyCode::Down => states.scroll_down(todos),
-        KeyCode::Up => states.scroll_up(),
-        KeyCode::Char('t') => todos.toggle(states.get_todo_list().selected().unwrap()),
-        KeyCode::Char('d') => todos.delete(states.get_todo_list().selected().unwrap()),
+        KeyCode::Up => states.scroll_up(todos),
+        KeyCode::Left => {
+            if let Some(id) = states.get_selected_id() {
+                // Collapses the todo, or goes up to its parent if it is already collapsed
+                if !todos.set_collapsed(id, true) {
+                    if let Some(parent) = todos.get_parent(id) {
+                        states.select_id(todos, parent);
+                    }
+                }
+            }
+        }
+        KeyCode::Right => {
+            if let Some(id) = states.get_selected_id() {
+                // Expands the todo, or goes down to its first subtask if it is already expanded
+                if !todos.set_collapsed(id, false) {
+                    if let Some(row) = todos.get_row(id) {
+                        let next = todos.get_id(row + 1);
+                        if next.is_some_and(|next| todos.get_parent(next) == Some(id)) {
+                            states.select_row(todos, row + 1);
+                        }
+                    }
+                }
+            }
+        }
+        KeyCode::Char('t') => {
+            if let Some(id) = states.get_selected_id() {
+                let offers_children =
+                    todos.completes_on_cycle(id) && todos.count_open_descendants(id) > 0;
+                match offers_children {
+                    true => states.set_pending_completion(Some(id)),
+                    false => {
+                        if let Err(message) = todos.cycle_status(id) {
+                            states.set_error_message(message);
+                        }
+                    }
+                }
+            }
+        }
+        KeyCode::Char('l') => {
+            if let Some(id) = states.get_selected_id() {
+                states.set_linking(Some(id));
+            }
+        }
+        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            states.open_finder(todos)
+        }
+        KeyCode::Char('u') => match todos.undo() {
+            Ok(Some(label)) => states.set_notice(Some(format!("Undone: {label}"))),
+            Ok(None) => states.set_notice(Some(String::from("Nothing to undo"))),
+            Err(error) => states.set_error_message(error),
+        },
+        KeyCode::Char('r') if redoes => match todos.redo() {
+            Ok(Some(label)) => states.set_notice(Some(format!("Redone: {label}"))),
+            Ok(None) => states.set_notice(Some(String::from("Nothing to redo"))),
+            Err(error) => states.set_error_message(error),
+        },
+        KeyCode::Char('/') => {
+            states.start_search();
+            let query = todos.get_search().cloned().unwrap_or_default();
+            update_search(todos, states, query);
+        }
+        KeyCode::Char('n') if todos.get_search().is_some() => jump_to_hit(todos, states, true),
+        KeyCode::Char('N') if todos.get_search().is_some() => jump_to_hit(todos, states, false),
+        KeyCode::Esc if todos.get_search().is_some() => todos.set_search(None),
+        KeyCode::Char('m') => {
+            if let Some(id) = states.get_selected_id() {
+                states.set_moving(Some(id));
+            }
+        }
+        KeyCode::Char('n') => states.set_new_list_name(Some(String::new())),
+        KeyCode::Tab => todos.cycle_list(1),
+        KeyCode::BackTab => todos.cycle_list(-1),
+        KeyCode::Char('d') => {
+            if let Some(id) = states.get_selected_id() {
+                states.set_pending_deletion(Some(id));
+            }
+        }
+        KeyCode::Char('T') => {
+            states.get_trash_list().select_first();
+            states.set_screen(Screens::Trash);
+        }
+        KeyCode::Char('x') => match todos.get_archivable(None).is_empty() {
+            true => states.set_notice(Some(String::from("No done todo to archive"))),
+            false => states.set_pending_archive(true),
+        },
+        KeyCode::Char('X') => {
+            states.get_archive_list().select_first();
+            states.set_screen(Screens::Archive);
+        }
+        KeyCode::Char('s') => todos.cycle_sort_key(),
+        KeyCode::Char('S') => todos.reverse_sort(),
+        KeyCode::Char('z') => todos.toggle_closed_last(),
+        KeyCode::Char('f') => todos.cycle_tag_filter(),
+        KeyCode::Char('F') => todos.clear_tag_filter(),
+        KeyCode::Char('w') => todos.cycle_time_filter(),
+        KeyCode::Char('W') => todos.clear_time_filter(),
         KeyCode::Char(ALL_KEY_EDIT) => {
             states.init_edit_mode(todos);
             states.set_screen(Screens::Edit);
         }
+        KeyCode::Char('b') if todos.keeps_backups() => {
+            states.get_backup_list().select_first();
+            states.set_screen(Screens::Backups);
+        }
         _ => {}
     }
     Ok(false)
@@ -75,11 +636,36 @@ pub fn handle_events(todos: &mut Todos, states: &mut States) -> std::io::Result<
             return Ok(false);
         }
 
-        match states.get_screen() {
-            Screens::Main => return handle_main_ui_events(todos, states, key),
-            Screens::Create => handle_create_ui_events(todos, states, key, false),
-            Screens::Edit => handle_create_ui_events(todos, states, key, true),
+        states.clear_error_message();
+        let quit = match states.get_screen() {
+            Screens::Main => handle_main_ui_events(todos, states, key)?,
+            Screens::Create => {
+                handle_create_ui_events(todos, states, key, false);
+                false
+            }
+            Screens::Edit => {
+                handle_create_ui_events(todos, states, key, true);
+                false
+            }
+            Screens::Backups => {
+                handle_backups_ui_events(todos, states, key);
+                false
+            }
+            Screens::Trash => {
+                handle_trash_ui_events(todos, states, key);
+                false
+            }
+            Screens::Archive => {
+                handle_archive_ui_events(todos, states, key);
+                false
+            }
+            Screens::Recovery => handle_recovery_ui_events(todos, states, key)?,
+        };
+        // Writes made along the way don't report their errors themselves
+        if let Some(error) = todos.take_error() {
+            states.set_error_message(error);
         }
+        return Ok(quit);
     }
     Ok(false)
 }
@@ -0,0 +1,73 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, List, ListState, Paragraph, StatefulWidget, Widget, Wrap},
+};
+
+/// Overlay listing the todos found by the fuzzy finder, with the description of the selected
+/// one next to them
+pub struct Finder<'a> {
+    pub query: &'a str,
+    pub results: Vec<Line<'a>>,
+    pub selected: usize,
+    pub preview: &'a str,
+    pub style: Style,
+    pub border_style: Style,
+}
+
+impl Finder<'_> {
+    /// The area of the popup centered in `area`
+    pub fn popup_area(area: Rect) -> Rect {
+        let [vertical] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(vertical);
+        popup
+    }
+}
+
+impl Widget for Finder<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(self.border_style)
+            .title(" Find a todo ")
+            .title_bottom(format!(" {} found ", self.results.len()));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [input, results_preview] =
+            Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(inner);
+        let [results, preview] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(results_preview);
+
+        Paragraph::new(format!("> {}_", self.query))
+            .style(self.style)
+            .bold()
+            .render(input, buf);
+        StatefulWidget::render(
+            List::new(self.results)
+                .style(self.style)
+                .highlight_style(Style::new().reversed()),
+            results,
+            buf,
+            &mut ListState::default().with_selected(Some(self.selected)),
+        );
+        Paragraph::new(self.preview)
+            .style(self.style)
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(self.border_style)
+                    .title(" Description "),
+            )
+            .render(preview, buf);
+    }
+}